@@ -93,7 +93,7 @@ function App() {
 
     const init = async (name: string, description: string, llm: LlmConfig, tools: ToolDefinition[]) => {
         try {
-            await api.initAgent({ name, description, llm, tools });
+            await api.initAgent({ name, description, llm, tools, askUser: true });
             setIsInitialized(true);
             addLog("Agent Initialized");
         } catch (e) {
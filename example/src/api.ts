@@ -61,7 +61,8 @@ export interface CancelTaskRequest {
 export const api = {
   initAgent: (config: InitAgentRequest) => invoke("plugin:radkit|init_agent", { config }),
   chat: (message: string, contextId?: string, taskId?: string) => invoke("plugin:radkit|chat", { message, contextId, taskId }),
-  streamChat: (message: string, contextId?: string, taskId?: string) => invoke("plugin:radkit|stream_chat", { message, contextId, taskId }),
+  streamChat: (message: string, contextId?: string, taskId?: string) => invoke<string>("plugin:radkit|stream_chat", { message, contextId, taskId }),
+  stopStream: (streamId: string) => invoke<void>("plugin:radkit|stop_stream", { streamId }),
   submitToolOutput: (payload: any) => invoke("plugin:radkit|submit_tool_output", { payload }),
   searchMemory: (request: SearchMemoryRequest) => invoke<MemoryEntryResult[]>("plugin:radkit|search_memory", { request }),
   saveMemory: (request: SaveMemoryRequest) => invoke<string>("plugin:radkit|save_memory", { request }),
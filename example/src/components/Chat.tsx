@@ -14,10 +14,15 @@ interface ChatProps {
     isInitialized: boolean;
 }
 
+function textOf(message: any): string {
+    return (message.parts || []).map((p: any) => p.text || "").join("");
+}
+
 export function Chat({ taskId, onTaskCreated, isInitialized }: ChatProps) {
     const [messages, setMessages] = useState<Message[]>([]);
     const [input, setInput] = useState("");
     const [loading, setLoading] = useState(false);
+    const [streamId, setStreamId] = useState<string>();
     const messagesEndRef = useRef<HTMLDivElement>(null);
 
     useEffect(() => {
@@ -26,33 +31,30 @@ export function Chat({ taskId, onTaskCreated, isInitialized }: ChatProps) {
 
     useEffect(() => {
         const handleStreamPayload = (payload: any) => {
-            let task = payload.Task || payload.task || (payload.kind === "task_update" ? payload.task : undefined);
-
-            if (!task && payload.task_id && payload.history) {
-                task = payload;
+            // Stream events arrive as `{ type, streamId, event }`; older
+            // recordings hold the bare A2A event.
+            const event = payload.type === "event" ? payload.event : payload;
+            if (!event) return;
+
+            if (event.kind === "task" && event.history) {
+                 const msgs: Message[] = event.history.map((h: any) => ({
+                     role: h.role === "agent" ? "assistant" : h.role.toLowerCase(),
+                     content: textOf(h),
+                 }));
+                 setMessages(msgs);
+            } else if (event.kind === "message" && event.role === "agent") {
+                 setMessages(prev => [...prev, { role: "assistant", content: textOf(event) }]);
             }
 
-            if (task) {
-                 if (task.history) {
-                     const msgs: Message[] = task.history.map((h: any) => {
-                         let content = "";
-                         if (h.parts) {
-                             h.parts.forEach((p: any) => {
-                                 if (p.text) content += p.text;
-                             });
-                         }
-                         return { role: h.role.toLowerCase(), content };
-                     });
-                     setMessages(msgs);
-                 }
-
-                 if (task.status === "completed" || task.status === "failed") {
-                     setLoading(false);
-                 }
+            const state = event.status?.state;
+            if (state && state !== "submitted" && state !== "working") {
+                 setLoading(false);
+                 setStreamId(undefined);
+            }
 
-                 if (task.task_id && !taskId) {
-                     onTaskCreated(task.task_id);
-                 }
+            const eventTaskId = event.kind === "task" ? event.id : event.taskId;
+            if (eventTaskId && !taskId) {
+                 onTaskCreated(eventTaskId);
             }
         };
 
@@ -61,6 +63,17 @@ export function Chat({ taskId, onTaskCreated, isInitialized }: ChatProps) {
             handleStreamPayload(event.payload);
         });
 
+        const unlistenEnd = Promise.all(["stream_completed", "stream_error"].map(name =>
+            listen(name, (event: any) => {
+                recorder.logEvent("stream", event.payload);
+                if (event.payload.type === "error") {
+                    setMessages(prev => [...prev, { role: "system", content: "Error: " + event.payload.error.message }]);
+                }
+                setLoading(false);
+                setStreamId(undefined);
+            })
+        ));
+
         const unlistenPlayback = listen("playback_event", (event: any) => {
              const entry = event.payload;
              if (entry.type === "prompt") {
@@ -76,6 +89,7 @@ export function Chat({ taskId, onTaskCreated, isInitialized }: ChatProps) {
 
         return () => {
             unlistenPromise.then(f => f());
+            unlistenEnd.then(fs => fs.forEach(f => f()));
             unlistenPlayback.then(f => f());
             unlistenStart.then(f => f());
         };
@@ -90,7 +104,7 @@ export function Chat({ taskId, onTaskCreated, isInitialized }: ChatProps) {
         recorder.logEvent("prompt", { content: userMsg });
 
         try {
-            await api.streamChat(userMsg, undefined, taskId);
+            setStreamId(await api.streamChat(userMsg, undefined, taskId));
             // Loading state will be handled by stream events
         } catch (e) {
             console.error(e);
@@ -100,6 +114,17 @@ export function Chat({ taskId, onTaskCreated, isInitialized }: ChatProps) {
         }
     };
 
+    const stopStream = async () => {
+        if (!streamId) return;
+        try {
+            await api.stopStream(streamId);
+        } catch (e) {
+            console.error(e);
+        }
+        setStreamId(undefined);
+        setLoading(false);
+    };
+
     useEffect(() => {
         if (taskId) {
             setMessages([]);
@@ -153,13 +178,22 @@ export function Chat({ taskId, onTaskCreated, isInitialized }: ChatProps) {
                         placeholder={isInitialized ? "Type a message..." : "Waiting for initialization..."}
                         disabled={!isInitialized || loading}
                     />
-                    <button
-                        className="bg-blue-600 px-4 py-2 rounded hover:bg-blue-500 disabled:opacity-50"
-                        onClick={sendMessage}
-                        disabled={!isInitialized || loading || !input.trim()}
-                    >
-                        Send
-                    </button>
+                    {streamId ? (
+                        <button
+                            className="bg-red-700 px-4 py-2 rounded hover:bg-red-600"
+                            onClick={stopStream}
+                        >
+                            Stop
+                        </button>
+                    ) : (
+                        <button
+                            className="bg-blue-600 px-4 py-2 rounded hover:bg-blue-500 disabled:opacity-50"
+                            onClick={sendMessage}
+                            disabled={!isInitialized || loading || !input.trim()}
+                        >
+                            Send
+                        </button>
+                    )}
                 </div>
             </div>
         </div>
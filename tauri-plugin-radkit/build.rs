@@ -1,4 +1,24 @@
-const COMMANDS: &[&str] = &["ping"];
+const COMMANDS: &[&str] = &[
+  "init_agent",
+  "validate_llm_config",
+  "update_llm",
+  "chat",
+  "stream_chat",
+  "stop_stream",
+  "submit_tool_output",
+  "list_tool_requests",
+  "store_credential",
+  "has_credential",
+  "delete_credential",
+  "set_auth_context",
+  "get_auth_context",
+  "search_memory",
+  "save_memory",
+  "delete_memory",
+  "list_tasks",
+  "get_task",
+  "cancel_task",
+];
 
 fn main() {
   tauri_plugin::Builder::new(COMMANDS)
@@ -1,23 +1,185 @@
-import { invoke } from '@tauri-apps/api/core'
+import { invoke, Channel } from '@tauri-apps/api/core'
 
+/** Error rejected by every plugin command, discriminated by `code`. */
+export type RadkitError =
+  | { code: "Io"; message: string }
+  | { code: "PluginInvoke"; message: string }
+  | { code: "NotInitialized"; message: string }
+  | { code: "InvalidRequest"; message: string }
+  | { code: "ProviderConfig"; message: string }
+  | { code: "ProviderHttp"; message: string; details: { status: number | null } }
+  | { code: "RateLimited"; message: string }
+  | { code: "ToolTimeout"; message: string; details: { tool: string; timeoutMs: number } }
+  | { code: "ToolRequestNotFound"; message: string; details: { requestId: string } }
+  | { code: "TaskNotFound"; message: string; details: { taskId: string } }
+  | { code: "StreamNotFound"; message: string; details: { streamId: string } }
+  | { code: "MemoryBackend"; message: string }
+  | { code: "SecretStore"; message: string }
+  | { code: "A2aProtocol"; message: string }
+  | { code: "ServerStart"; message: string }
+  | { code: "Agent"; message: string; details: { component: string } }
+  | { code: "Serialization"; message: string }
+  | { code: "Tauri"; message: string };
+
+export type RadkitErrorCode = RadkitError["code"];
+
+export function isRadkitError(error: unknown): error is RadkitError {
+  return typeof error === "object" && error !== null && "code" in error && "message" in error;
+}
+
+export type ReasoningEffort = "low" | "medium" | "high";
+
+export type ResponseFormat =
+  | { type: "text" }
+  | { type: "jsonObject" }
+  | { type: "jsonSchema"; name: string; schema: any; strict?: boolean };
+
+/**
+ * Generation options. `Anthropic` and `Gemini` accept only `temperature` and
+ * `maxTokens`, and `DeepSeek` everything but `seed` and `reasoningEffort`;
+ * setting an option the provider does not accept fails with `ProviderConfig`.
+ */
 export interface CommonLlmConfig {
   temperature?: number;
   maxTokens?: number;
   topP?: number;
+  stop?: string[];
+  seed?: number;
+  reasoningEffort?: ReasoningEffort;
+  responseFormat?: ResponseFormat;
+  /** Sent with every request to the provider. */
+  headers?: Record<string, string>;
+  /** Merged into the request body as-is, last. */
+  extra?: Record<string, any>;
 }
 
 export type LlmConfig =
-  | ({ provider: "OpenAI"; model: string; apiKey?: string } & CommonLlmConfig)
-  | ({ provider: "Anthropic"; model: string; apiKey?: string } & CommonLlmConfig)
-  | ({ provider: "Gemini"; model: string; apiKey?: string } & CommonLlmConfig)
-  | ({ provider: "OpenRouter"; model: string; apiKey?: string; siteUrl?: string; appName?: string } & CommonLlmConfig)
-  | ({ provider: "Grok"; model: string; apiKey?: string } & CommonLlmConfig)
-  | ({ provider: "DeepSeek"; model: string; apiKey?: string } & CommonLlmConfig);
+  | ({ provider: "OpenAI"; model: string; apiKey?: string; credential?: string } & CommonLlmConfig)
+  | ({ provider: "Anthropic"; model: string; apiKey?: string; credential?: string } & CommonLlmConfig)
+  | ({ provider: "Gemini"; model: string; apiKey?: string; credential?: string } & CommonLlmConfig)
+  | ({ provider: "OpenRouter"; model: string; apiKey?: string; credential?: string; siteUrl?: string; appName?: string } & CommonLlmConfig)
+  | ({ provider: "Grok"; model: string; apiKey?: string; credential?: string } & CommonLlmConfig)
+  | ({ provider: "DeepSeek"; model: string; apiKey?: string; credential?: string } & CommonLlmConfig)
+  | ({ provider: "OpenAICompatible"; baseUrl: string; model: string; apiKey?: string } & CommonLlmConfig)
+  | { provider: "Scripted"; model?: string; turns?: ScriptedTurn[]; scriptPath?: string };
+
+export interface ScriptedToolCall {
+  id?: string;
+  name: string;
+  arguments?: any;
+}
+
+export interface ScriptedTurn {
+  text?: string;
+  toolCalls?: ScriptedToolCall[];
+}
 
 export interface ToolDefinition {
   name: string;
   description: string;
   parameters: any;
+  timeoutMs?: number;
+}
+
+export interface ToolOutputPayload {
+  requestId: string;
+  result: any;
+  isError: boolean;
+  inputRequired?: string;
+}
+
+/** Payload of the `tool_execution_request` event, also returned by `listToolRequests`. */
+export interface ToolRequestInfo {
+  requestId: string;
+  name: string;
+  args: Record<string, any>;
+  taskId?: string;
+  contextId?: string;
+  toolCallId?: string;
+  sequence: number;
+}
+
+export interface ListToolRequestsRequest {
+  taskId?: string;
+  contextId?: string;
+}
+
+/** Payload of the `tool_execution_cancelled` event. */
+export interface ToolExecutionCancelledEvent {
+  requestId: string;
+  name: string;
+  taskId?: string;
+  contextId?: string;
+  toolCallId?: string;
+  reason: "timeout" | "cancelled" | "aborted";
+}
+
+/** Payload of the `stream_event` event. */
+export interface StreamEvent {
+  type: "event";
+  streamId: string;
+  /** The A2A streaming event: a message, task, status update or artifact update. */
+  event: any;
+}
+
+/** Payload of the `stream_started` event, sent before the stream's first event. */
+export interface StreamStartedEvent {
+  type: "started";
+  streamId: string;
+  taskId?: string;
+  contextId?: string;
+}
+
+/** Payload of the `stream_completed` event, sent once the stream has ended normally. */
+export interface StreamCompletedEvent {
+  type: "completed";
+  streamId: string;
+  taskId?: string;
+  contextId?: string;
+  /** Last task state the stream reported, e.g. "completed" or "input-required". */
+  state?: string;
+}
+
+/** Payload of the `stream_error` event, sent instead of `stream_completed` when the stream fails. */
+export interface StreamErrorEvent {
+  type: "error";
+  streamId: string;
+  taskId?: string;
+  error: RadkitError;
+}
+
+/**
+ * Data part of the status updates the agent sends around each tool call, in
+ * the stream and in the task history.
+ */
+export interface ToolCallProgress {
+  type: "toolCall";
+  phase: "calling" | "returned" | "failed";
+  name: string;
+  toolCallId: string;
+  /** Only set when `phase` is "calling". */
+  args?: Record<string, any>;
+  /** Only set when `phase` is "failed". */
+  error?: string;
+}
+
+/** Everything a stream reports, as delivered to `streamChat`'s `onEvent` callback. */
+export type StreamMessage = StreamStartedEvent | StreamEvent | StreamCompletedEvent | StreamErrorEvent;
+
+/**
+ * Data part of the status updates carrying a piece of the model's reply
+ * while it is generated. Only sent to the stream, not kept in the history.
+ */
+export interface TextDelta {
+  type: "textDelta";
+  text: string;
+}
+
+/** Payload of the `task_input_required` event. */
+export interface InputRequiredEvent {
+  taskId: string;
+  contextId: string;
+  message: any;
 }
 
 export interface InitAgentRequest {
@@ -25,6 +187,55 @@ export interface InitAgentRequest {
   description: string;
   llm: LlmConfig;
   tools: ToolDefinition[];
+  cassette?: CassetteConfig;
+  systemPrompt?: string;
+  /** Time to wait for a frontend tool's output. Defaults to 5 minutes; `0` waits indefinitely. */
+  toolTimeoutMs?: number;
+  taskStore?: TaskStoreConfig;
+  memory?: MemoryStoreConfig;
+  /** Offer the model the built-in `ask_user` tool. Defaults to `false`. */
+  askUser?: boolean;
+}
+
+export type TaskStoreConfig =
+  | { type: "memory" }
+  | { type: "file"; path?: string };
+
+export type MemoryStoreConfig =
+  | { type: "memory" }
+  | { type: "file"; path?: string; embedding?: EmbeddingConfig };
+
+export type EmbeddingConfig =
+  | { type: "hashed"; dimensions?: number }
+  /** `credential` names a stored key; it cannot be combined with `baseUrl`. */
+  | { type: "openai"; model?: string; apiKey?: string; credential?: string; baseUrl?: string };
+
+export interface SetAuthContextRequest {
+  /** Tenant the user belongs to; keeps the current one when omitted. */
+  appName?: string;
+  userName: string;
+}
+
+export interface AuthContextInfo {
+  appName: string;
+  userName: string;
+}
+
+export interface ChatOptions {
+  variables?: Record<string, string>;
+  /** Model to use for this message instead of the configured one, on the same provider. */
+  model?: string;
+  temperature?: number;
+  maxTokens?: number;
+  /** Names of the tools the model may call for this message. All tools are offered when unset. */
+  tools?: string[];
+}
+
+export type CassetteMode = "record" | "replay" | "passthrough";
+
+export interface CassetteConfig {
+  path: string;
+  mode: CassetteMode;
 }
 
 export interface MemoryEntryResult {
@@ -62,18 +273,84 @@ export async function initAgent(config: InitAgentRequest): Promise<void> {
   await invoke('plugin:radkit|init_agent', { config });
 }
 
-export async function chat(message: string, contextId?: string, taskId?: string): Promise<any> {
-  return await invoke('plugin:radkit|chat', { message, contextId, taskId });
+/** Checks an LLM config without starting an agent or contacting the provider. */
+export async function validateLlmConfig(config: LlmConfig): Promise<void> {
+  await invoke('plugin:radkit|validate_llm_config', { config });
+}
+
+/**
+ * Replaces the running agent's LLM, e.g. after a model or temperature change
+ * in settings. Tasks, tools and conversations are kept. Needs the
+ * `radkit:allow-update-llm` permission, which is not in the default set.
+ */
+export async function updateLlm(config: LlmConfig): Promise<void> {
+  await invoke('plugin:radkit|update_llm', { config });
+}
+
+/**
+ * Sends a message and waits for the reply. Only one task runs in a
+ * conversation at a time: while one in `contextId` is working, starting
+ * another rejects with `InvalidRequest`.
+ */
+export async function chat(message: string, contextId?: string, taskId?: string, options?: ChatOptions): Promise<any> {
+  return await invoke('plugin:radkit|chat', { message, contextId, taskId, options });
+}
+
+/**
+ * Starts a streaming reply and returns its stream ID. With `onEvent`, this
+ * stream's messages are delivered, in order, only to that callback; without it
+ * they are broadcast as `stream_started`, `stream_event`, `stream_completed`
+ * and `stream_error`.
+ */
+export async function streamChat(
+  message: string,
+  contextId?: string,
+  taskId?: string,
+  options?: ChatOptions,
+  onEvent?: (message: StreamMessage) => void,
+): Promise<string> {
+  let channel: Channel<StreamMessage> | undefined;
+  if (onEvent) {
+    channel = new Channel<StreamMessage>();
+    channel.onmessage = onEvent;
+  }
+  return await invoke('plugin:radkit|stream_chat', { message, contextId, taskId, options, onEvent: channel });
 }
 
-export async function streamChat(message: string, contextId?: string, taskId?: string): Promise<void> {
-  await invoke('plugin:radkit|stream_chat', { message, contextId, taskId });
+export async function stopStream(streamId: string): Promise<void> {
+  await invoke('plugin:radkit|stop_stream', { streamId });
 }
 
-export async function submitToolOutput(payload: any): Promise<void> {
+export async function submitToolOutput(payload: ToolOutputPayload): Promise<void> {
   await invoke('plugin:radkit|submit_tool_output', { payload });
 }
 
+export async function listToolRequests(request: ListToolRequestsRequest = {}): Promise<ToolRequestInfo[]> {
+  return await invoke('plugin:radkit|list_tool_requests', { request });
+}
+
+/** Saves a provider key in the Rust-side secret store under `name`, for `LlmConfig.credential` and `EmbeddingConfig.credential`. */
+export async function storeCredential(name: string, secret: string): Promise<void> {
+  await invoke('plugin:radkit|store_credential', { request: { name, secret } });
+}
+
+/** Reports whether a credential is stored. The secret itself is never returned. */
+export async function hasCredential(name: string): Promise<boolean> {
+  return await invoke('plugin:radkit|has_credential', { request: { name } });
+}
+
+export async function deleteCredential(name: string): Promise<boolean> {
+  return await invoke('plugin:radkit|delete_credential', { request: { name } });
+}
+
+export async function setAuthContext(request: SetAuthContextRequest): Promise<void> {
+  await invoke('plugin:radkit|set_auth_context', { request });
+}
+
+export async function getAuthContext(): Promise<AuthContextInfo> {
+  return await invoke('plugin:radkit|get_auth_context');
+}
+
 export async function searchMemory(request: SearchMemoryRequest): Promise<MemoryEntryResult[]> {
   return await invoke('plugin:radkit|search_memory', { request });
 }
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+use radkit::models::BaseLlm;
+use serde_json::Value;
+use tauri::{Manager, Runtime, State};
+use tokio::sync::mpsc;
+use crate::commands;
+use crate::models::*;
+use crate::runtime_holder::RadkitRuntimeState;
+use crate::streams::{StreamMessage, StreamSink, STREAM_BUFFER};
+use crate::{Radkit, Result};
+
+/// The same operations as the plugin's commands, for Rust code in the host
+/// app. Unlike the `init_agent` command, `init_agent` here is always allowed.
+impl<R: Runtime> Radkit<R> {
+  fn state(&self) -> State<'_, RadkitRuntimeState> {
+    self.app().state::<RadkitRuntimeState>()
+  }
+
+  pub async fn init_agent(&self, config: InitAgentRequest) -> Result<InitResponse> {
+    commands::start_agent(self.app(), &self.state(), config).await
+  }
+
+  pub async fn validate_llm_config(&self, config: LlmConfig) -> Result<()> {
+    self.build_llm(config).map(|_| ())
+  }
+
+  pub async fn update_llm(&self, config: LlmConfig) -> Result<()> {
+    commands::swap_llm(&self.state(), config)
+  }
+
+  /// Builds a provider from `config`, resolving stored credentials, for use
+  /// outside the agent.
+  pub fn build_llm(&self, config: LlmConfig) -> Result<Arc<dyn BaseLlm>> {
+    crate::llm_factory::build_llm(config, &self.state().secrets)
+  }
+
+  pub async fn chat(
+    &self,
+    message: String,
+    context_id: Option<String>,
+    task_id: Option<String>,
+    options: Option<ChatOptions>,
+  ) -> Result<Value> {
+    commands::chat(self.app().clone(), self.state(), message, context_id, task_id, options).await
+  }
+
+  /// Starts a streaming reply. Returns the stream ID and a receiver for the
+  /// stream's messages, which are not broadcast to the frontend. The stream
+  /// pauses while `STREAM_BUFFER` messages are waiting to be received.
+  pub async fn stream_chat(
+    &self,
+    message: String,
+    context_id: Option<String>,
+    task_id: Option<String>,
+    options: Option<ChatOptions>,
+  ) -> Result<(String, mpsc::Receiver<StreamMessage>)> {
+    let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+    let stream_id = commands::start_stream(
+      self.app(),
+      &self.state(),
+      message,
+      context_id,
+      task_id,
+      options,
+      StreamSink::Sender(sender),
+    )
+    .await?;
+    Ok((stream_id, receiver))
+  }
+
+  pub async fn stop_stream(&self, stream_id: String) -> Result<()> {
+    commands::stop_stream(self.app().clone(), self.state(), stream_id).await
+  }
+
+  pub async fn submit_tool_output(&self, payload: ToolOutputRequest) -> Result<()> {
+    commands::submit_tool_output(self.state(), payload).await
+  }
+
+  pub async fn list_tool_requests(&self, request: ListToolRequestsRequest) -> Result<Vec<ToolRequestInfo>> {
+    commands::list_tool_requests(self.state(), request).await
+  }
+
+  /// Saves a provider key in the secret store under `name`, for
+  /// `LlmConfig` and `EmbeddingConfig` credentials.
+  pub async fn store_credential(&self, name: String, secret: String) -> Result<()> {
+    commands::store_credential(self.state(), StoreCredentialRequest { name, secret }).await
+  }
+
+  pub async fn has_credential(&self, name: String) -> Result<bool> {
+    commands::has_credential(self.state(), CredentialRequest { name }).await
+  }
+
+  pub async fn delete_credential(&self, name: String) -> Result<bool> {
+    commands::delete_credential(self.state(), CredentialRequest { name }).await
+  }
+
+  /// Switches the active user, e.g. when the app changes workspace.
+  pub async fn set_auth_context(&self, request: SetAuthContextRequest) -> Result<()> {
+    commands::set_auth_context(self.state(), request).await
+  }
+
+  /// The user and tenant that memory calls and A2A requests are scoped to.
+  pub async fn get_auth_context(&self) -> Result<AuthContextInfo> {
+    commands::get_auth_context(self.state()).await
+  }
+
+  pub async fn search_memory(&self, request: SearchMemoryRequest) -> Result<Vec<MemoryEntryResult>> {
+    commands::search_memory(self.state(), request).await
+  }
+
+  pub async fn save_memory(&self, request: SaveMemoryRequest) -> Result<String> {
+    commands::save_memory(self.state(), request).await
+  }
+
+  pub async fn delete_memory(&self, id: String) -> Result<bool> {
+    commands::delete_memory(self.state(), DeleteMemoryRequest { id }).await
+  }
+
+  pub async fn list_tasks(&self, context_id: Option<String>) -> Result<Vec<Value>> {
+    commands::list_tasks(self.state(), ListTasksRequest { context_id }).await
+  }
+
+  pub async fn get_task(&self, task_id: String) -> Result<Value> {
+    commands::get_task(self.state(), GetTaskRequest { task_id }).await
+  }
+
+  pub async fn cancel_task(&self, task_id: String) -> Result<Value> {
+    commands::cancel_task(self.app().clone(), self.state(), CancelTaskRequest { task_id }).await
+  }
+}
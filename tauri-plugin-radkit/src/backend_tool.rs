@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde_json::Value;
+use radkit::tools::{BaseTool, FunctionDeclaration, ToolContext, ToolResult};
+
+/// A tool registered by the host app in Rust. The same instance is shared by
+/// every agent `init_agent` builds, so it is held behind an `Arc`.
+#[derive(Clone)]
+pub struct BackendTool(pub Arc<dyn BaseTool>);
+
+#[async_trait::async_trait]
+impl BaseTool for BackendTool {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn description(&self) -> &str {
+        self.0.description()
+    }
+
+    fn declaration(&self) -> FunctionDeclaration {
+        self.0.declaration()
+    }
+
+    async fn run_async(
+        &self,
+        args: HashMap<String, Value>,
+        context: &ToolContext<'_>,
+    ) -> ToolResult {
+        self.0.run_async(args, context).await
+    }
+}
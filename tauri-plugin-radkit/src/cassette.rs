@@ -0,0 +1,191 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use radkit::models::{LlmResponse, Thread};
+use radkit::tools::BaseToolset;
+use radkit::errors::{AgentError, AgentResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::models::CassetteMode;
+use crate::Error;
+
+/// One recorded `generate_content` exchange.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Interaction {
+    thread: Value,
+    tools: Vec<Value>,
+    response: LlmResponse,
+}
+
+/// Records LLM exchanges to a JSON file, or replays them from it in order.
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    interactions: Mutex<Vec<Interaction>>,
+    cursor: AtomicUsize,
+}
+
+impl Cassette {
+    pub fn open(path: impl Into<PathBuf>, mode: CassetteMode) -> crate::Result<Self> {
+        let path = path.into();
+        let interactions = match mode {
+            CassetteMode::Replay => {
+                let raw = std::fs::read_to_string(&path).map_err(|e| {
+                    Error::InvalidRequest(format!("Failed to read cassette {}: {}", path.display(), e))
+                })?;
+                serde_json::from_str(&raw).map_err(|e| {
+                    Error::InvalidRequest(format!("Invalid cassette {}: {}", path.display(), e))
+                })?
+            }
+            CassetteMode::Record | CassetteMode::Passthrough => Vec::new(),
+        };
+
+        Ok(Self {
+            path,
+            mode,
+            interactions: Mutex::new(interactions),
+            cursor: AtomicUsize::new(0),
+        })
+    }
+
+    /// Answers one LLM request: through `call`, which sends it to the
+    /// provider, unless the cassette is replaying.
+    pub async fn generate<F, Fut>(
+        &self,
+        thread: Thread,
+        toolset: Option<Arc<dyn BaseToolset>>,
+        call: F,
+    ) -> AgentResult<LlmResponse>
+    where
+        F: FnOnce(Thread, Option<Arc<dyn BaseToolset>>) -> Fut,
+        Fut: Future<Output = AgentResult<LlmResponse>>,
+    {
+        match self.mode {
+            CassetteMode::Passthrough => call(thread, toolset).await,
+            CassetteMode::Record => {
+                let (thread_key, tools_key) = request_key(&thread, toolset.as_ref()).await?;
+                let response = call(thread, toolset).await?;
+                self.record(Interaction {
+                    thread: thread_key,
+                    tools: tools_key,
+                    response: response.clone(),
+                })?;
+                Ok(response)
+            }
+            CassetteMode::Replay => {
+                let (thread_key, tools_key) = request_key(&thread, toolset.as_ref()).await?;
+                self.replay(thread_key, tools_key)
+            }
+        }
+    }
+
+    fn record(&self, interaction: Interaction) -> AgentResult<()> {
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push(interaction);
+        let json = serde_json::to_string_pretty(&*interactions).map_err(cassette_error)?;
+        std::fs::write(&self.path, json).map_err(cassette_error)
+    }
+
+    fn replay(&self, thread: Value, tools: Vec<Value>) -> AgentResult<LlmResponse> {
+        let index = self.cursor.fetch_add(1, Ordering::SeqCst);
+        let interactions = self.interactions.lock().unwrap();
+        let interaction = interactions.get(index).ok_or_else(|| {
+            cassette_error(format!("No recorded interaction #{} in {}", index, self.path.display()))
+        })?;
+
+        if interaction.thread != thread || interaction.tools != tools {
+            return Err(cassette_error(format!(
+                "Request #{} does not match the recording in {}",
+                index,
+                self.path.display()
+            )));
+        }
+        Ok(interaction.response.clone())
+    }
+}
+
+async fn request_key(
+    thread: &Thread,
+    toolset: Option<&Arc<dyn BaseToolset>>,
+) -> AgentResult<(Value, Vec<Value>)> {
+    let thread = serde_json::to_value(thread).map_err(cassette_error)?;
+    let mut tools = Vec::new();
+    if let Some(toolset) = toolset {
+        for tool in toolset.get_tools().await {
+            tools.push(serde_json::to_value(tool.declaration()).map_err(cassette_error)?);
+        }
+    }
+    Ok((thread, tools))
+}
+
+fn cassette_error(e: impl ToString) -> AgentError {
+    AgentError::Internal {
+        component: "Cassette".into(),
+        reason: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use radkit::models::{Content, TokenUsage};
+
+    /// A fresh cassette path in the temporary directory.
+    fn cassette_path() -> PathBuf {
+        std::env::temp_dir().join(format!("radkit-cassette-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    fn reply(text: &str) -> LlmResponse {
+        LlmResponse::new(Content::from_text(text), TokenUsage::empty())
+    }
+
+    /// Replays a request, failing the test if the provider would be called.
+    async fn replay(cassette: &Cassette, message: &str) -> AgentResult<LlmResponse> {
+        cassette
+            .generate(Thread::from_user(message), None, |_, _| async { panic!("replay called the provider") })
+            .await
+    }
+
+    #[tokio::test]
+    async fn recorded_replies_are_replayed_in_order() {
+        let path = cassette_path();
+        let recorder = Cassette::open(&path, CassetteMode::Record).unwrap();
+        for (message, answer) in [("Hello", "Hi there"), ("Bye", "Goodbye")] {
+            let response = recorder
+                .generate(Thread::from_user(message), None, |_, _| async move { Ok(reply(answer)) })
+                .await
+                .unwrap();
+            assert_eq!(response.content().first_text(), Some(answer));
+        }
+
+        let player = Cassette::open(&path, CassetteMode::Replay).unwrap();
+        assert_eq!(replay(&player, "Hello").await.unwrap().content().first_text(), Some("Hi there"));
+        assert_eq!(replay(&player, "Bye").await.unwrap().content().first_text(), Some("Goodbye"));
+        let error = replay(&player, "Again").await.unwrap_err();
+        assert!(error.to_string().contains("No recorded interaction #2"), "{}", error);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replay_rejects_a_request_that_differs_from_the_recording() {
+        let path = cassette_path();
+        let recorder = Cassette::open(&path, CassetteMode::Record).unwrap();
+        recorder
+            .generate(Thread::from_user("Hello"), None, |_, _| async { Ok(reply("Hi there")) })
+            .await
+            .unwrap();
+
+        let player = Cassette::open(&path, CassetteMode::Replay).unwrap();
+        let error = replay(&player, "Hello, again").await.unwrap_err();
+        assert!(error.to_string().contains("Request #0 does not match"), "{}", error);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_cassette_is_an_invalid_request() {
+        let error = Cassette::open(cassette_path(), CassetteMode::Replay).err().unwrap();
+        assert!(matches!(error, Error::InvalidRequest(_)));
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use radkit::models::{BaseLlm, Content, ContentPart, DataSource, Event, LlmResponse, Role, Thread, TokenUsage};
+use radkit::tools::{BaseToolset, ToolCall};
+use radkit::errors::{AgentError, AgentResult};
+use serde_json::{json, Map, Value};
+use tokio::sync::mpsc::UnboundedSender;
+use crate::models::{CommonLlmConfig, ResponseFormat};
+
+/// A client for the OpenAI chat completions API, spoken by OpenAI, Grok,
+/// DeepSeek, OpenRouter and most local servers. Unlike radkit's providers it
+/// can stream the reply, and it sends every `CommonLlmConfig` option.
+pub struct ChatCompletionsLlm {
+    /// Provider name used in errors.
+    provider: &'static str,
+    model: String,
+    /// Full URL of the chat completions endpoint.
+    url: String,
+    api_key: Option<String>,
+    headers: HashMap<String, String>,
+    /// Fields added to every request body, after the messages and tools.
+    options: Map<String, Value>,
+    client: reqwest::Client,
+}
+
+impl ChatCompletionsLlm {
+    pub fn new(provider: &'static str, model: String, url: String, api_key: Option<String>) -> Self {
+        Self {
+            provider,
+            model,
+            url,
+            api_key,
+            headers: HashMap::new(),
+            options: Map::new(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Sets a request body field, replacing any option of the same name.
+    pub fn with_option(mut self, name: &str, value: Value) -> Self {
+        self.options.insert(name.to_string(), value);
+        self
+    }
+
+    /// Applies `common` to every request. `extra` is merged last, so it can
+    /// override any other field.
+    pub fn with_common(mut self, common: CommonLlmConfig) -> Self {
+        let CommonLlmConfig {
+            temperature,
+            max_tokens,
+            top_p,
+            stop,
+            seed,
+            reasoning_effort,
+            response_format,
+            headers,
+            extra,
+        } = common;
+        let fields = [
+            ("temperature", temperature.map(|t| json!(t))),
+            ("max_tokens", max_tokens.map(|m| json!(m))),
+            ("top_p", top_p.map(|p| json!(p))),
+            ("stop", stop.map(|s| json!(s))),
+            ("seed", seed.map(|s| json!(s))),
+            ("reasoning_effort", reasoning_effort.map(|e| json!(e))),
+            ("response_format", response_format.map(response_format_value)),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                self.options.insert(name.to_string(), value);
+            }
+        }
+        self.headers.extend(headers.unwrap_or_default());
+        self.options.extend(extra.unwrap_or_default());
+        self
+    }
+
+    /// Generates a reply, sending each piece of its text to `deltas` as the
+    /// provider produces it.
+    pub async fn generate_streaming(
+        &self,
+        thread: Thread,
+        toolset: Option<Arc<dyn BaseToolset>>,
+        deltas: &UnboundedSender<String>,
+    ) -> AgentResult<LlmResponse> {
+        let mut payload = self.payload(thread, toolset).await;
+        payload["stream"] = json!(true);
+        payload["stream_options"] = json!({ "include_usage": true });
+        let mut response = self.send(&payload).await?;
+
+        let mut reply = StreamedReply::default();
+        let mut buffer = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                    continue;
+                };
+                if data == "[DONE]" {
+                    return reply.finish(self.provider);
+                }
+                let event: Value = serde_json::from_str(data).map_err(|e| self.error(format!("Invalid stream event: {}", e)))?;
+                if let Some(error) = event.get("error") {
+                    return Err(self.error(error.to_string()));
+                }
+                if let Some(text) = reply.apply(&event) {
+                    // The caller may stop listening; the reply is still collected.
+                    let _ = deltas.send(text);
+                }
+            }
+        }
+        // Some servers close the stream without sending `[DONE]`.
+        reply.finish(self.provider)
+    }
+
+    async fn payload(&self, thread: Thread, toolset: Option<Arc<dyn BaseToolset>>) -> Value {
+        let (system_prompt, events) = thread.into_parts();
+        let mut messages = Vec::new();
+        if let Some(system) = system_prompt {
+            messages.push(json!({ "role": "system", "content": system }));
+        }
+        for event in events {
+            push_message(event, &mut messages);
+        }
+
+        let mut payload = json!({ "model": self.model, "messages": messages });
+        if let Some(toolset) = toolset {
+            let tools: Vec<Value> = toolset
+                .get_tools()
+                .await
+                .iter()
+                .map(|tool| {
+                    let declaration = tool.declaration();
+                    json!({
+                        "type": "function",
+                        "function": {
+                            "name": declaration.name(),
+                            "description": declaration.description(),
+                            "parameters": declaration.parameters(),
+                            "strict": false,
+                        }
+                    })
+                })
+                .collect();
+            if !tools.is_empty() {
+                payload["tools"] = json!(tools);
+            }
+        }
+        for (name, value) in &self.options {
+            payload[name.as_str()] = value.clone();
+        }
+        payload
+    }
+
+    async fn send(&self, payload: &Value) -> AgentResult<reqwest::Response> {
+        let mut request = self.client.post(&self.url).json(payload);
+        if let Some(key) = self.api_key.as_deref().filter(|k| !k.is_empty()) {
+            request = request.bearer_auth(key);
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        Err(match status.as_u16() {
+            401 | 403 => AgentError::LlmAuthentication { provider: self.provider.to_string() },
+            429 => AgentError::LlmRateLimit { provider: self.provider.to_string() },
+            _ => self.error(format!("HTTP {}: {}", status, body)),
+        })
+    }
+
+    fn error(&self, message: String) -> AgentError {
+        AgentError::LlmProvider {
+            provider: self.provider.to_string(),
+            message,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BaseLlm for ChatCompletionsLlm {
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    async fn generate_content(
+        &self,
+        thread: Thread,
+        toolset: Option<Arc<dyn BaseToolset>>,
+    ) -> AgentResult<LlmResponse> {
+        let payload = self.payload(thread, toolset).await;
+        let body: Value = self.send(&payload).await?.json().await?;
+        let message = body
+            .get("choices")
+            .and_then(|choices| choices.get(0))
+            .and_then(|choice| choice.get("message"))
+            .ok_or_else(|| self.error("Missing 'choices[0].message' in response".into()))?;
+
+        let mut parts = Vec::new();
+        if let Some(text) = message.get("content").and_then(Value::as_str).map(str::trim).filter(|t| !t.is_empty()) {
+            parts.push(ContentPart::Text(text.to_string()));
+        }
+        for call in message.get("tool_calls").and_then(Value::as_array).into_iter().flatten() {
+            let function = &call["function"];
+            let (Some(id), Some(name)) = (call["id"].as_str(), function["name"].as_str()) else {
+                return Err(self.error("Tool call without an id or name in response".into()));
+            };
+            parts.push(ContentPart::ToolCall(ToolCall::new(id, name, arguments(&function["arguments"]))));
+        }
+        Ok(LlmResponse::new(Content::from_parts(parts), usage(self.provider, body.get("usage"))))
+    }
+}
+
+/// A reply assembled from stream events.
+#[derive(Default)]
+struct StreamedReply {
+    text: String,
+    /// Tool calls by the index the provider gives them, since their id, name
+    /// and arguments can each arrive in pieces.
+    calls: BTreeMap<u64, StreamedCall>,
+    usage: Option<Value>,
+}
+
+#[derive(Default)]
+struct StreamedCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+impl StreamedReply {
+    /// Adds one stream event to the reply and returns its new text, if any.
+    fn apply(&mut self, event: &Value) -> Option<String> {
+        if let Some(usage) = event.get("usage").filter(|u| !u.is_null()) {
+            self.usage = Some(usage.clone());
+        }
+        let delta = event.get("choices")?.get(0)?.get("delta")?;
+        for call in delta.get("tool_calls").and_then(Value::as_array).into_iter().flatten() {
+            let index = call["index"].as_u64().unwrap_or(self.calls.len() as u64);
+            let entry = self.calls.entry(index).or_default();
+            if let Some(id) = call["id"].as_str() {
+                entry.id.push_str(id);
+            }
+            if let Some(name) = call["function"]["name"].as_str() {
+                entry.name.push_str(name);
+            }
+            if let Some(arguments) = call["function"]["arguments"].as_str() {
+                entry.arguments.push_str(arguments);
+            }
+        }
+        let text = delta.get("content").and_then(Value::as_str).filter(|t| !t.is_empty())?;
+        self.text.push_str(text);
+        Some(text.to_string())
+    }
+
+    fn finish(self, provider: &str) -> AgentResult<LlmResponse> {
+        let mut parts = Vec::new();
+        let text = self.text.trim();
+        if !text.is_empty() {
+            parts.push(ContentPart::Text(text.to_string()));
+        }
+        for call in self.calls.into_values() {
+            if call.id.is_empty() || call.name.is_empty() {
+                return Err(AgentError::LlmProvider {
+                    provider: provider.to_string(),
+                    message: "Tool call without an id or name in stream".into(),
+                });
+            }
+            let arguments = arguments(&Value::String(call.arguments));
+            parts.push(ContentPart::ToolCall(ToolCall::new(call.id, call.name, arguments)));
+        }
+        Ok(LlmResponse::new(Content::from_parts(parts), usage(provider, self.usage.as_ref())))
+    }
+}
+
+/// Converts one thread event to chat completions messages, the way radkit's
+/// OpenAI provider does.
+fn push_message(event: Event, messages: &mut Vec<Value>) {
+    let role = *event.role();
+    let content = event.into_content();
+    match role {
+        Role::System | Role::User => {
+            let role = match role {
+                Role::System => "system",
+                _ => "user",
+            };
+            if content.is_text_only() && !content.is_text_empty() {
+                messages.push(json!({ "role": role, "content": content.joined_texts().unwrap_or_default() }));
+                return;
+            }
+            let parts: Vec<Value> = content
+                .into_iter()
+                .filter_map(|part| match part {
+                    ContentPart::Text(text) => Some(json!({ "type": "text", "text": text })),
+                    ContentPart::Data(data) if data.content_type.starts_with("image/") => {
+                        let url = match data.source {
+                            DataSource::Base64(encoded) => format!("data:{};base64,{}", data.content_type, encoded),
+                            DataSource::Uri(uri) => uri,
+                        };
+                        Some(json!({ "type": "image_url", "image_url": { "url": url } }))
+                    }
+                    _ => None,
+                })
+                .collect();
+            if !parts.is_empty() {
+                messages.push(json!({ "role": role, "content": parts }));
+            }
+        }
+        Role::Assistant => {
+            let mut texts = Vec::new();
+            let mut calls = Vec::new();
+            for part in content {
+                match part {
+                    ContentPart::Text(text) => texts.push(text),
+                    ContentPart::ToolCall(call) => calls.push(json!({
+                        "type": "function",
+                        "id": call.id(),
+                        "function": { "name": call.name(), "arguments": call.arguments().to_string() },
+                    })),
+                    _ => {}
+                }
+            }
+            let mut message = json!({ "role": "assistant", "content": texts.join("\n\n") });
+            if !calls.is_empty() {
+                message["tool_calls"] = json!(calls);
+            }
+            messages.push(message);
+        }
+        Role::Tool => {
+            for part in content {
+                if let ContentPart::ToolResponse(response) = part {
+                    let result = response.result();
+                    let output = if result.is_success() {
+                        result.data().to_string()
+                    } else {
+                        json!({ "error": result.error_message().unwrap_or("Unknown error") }).to_string()
+                    };
+                    messages.push(json!({
+                        "role": "tool",
+                        "content": output,
+                        "tool_call_id": response.tool_call_id(),
+                    }));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Tool call arguments arrive as a JSON-encoded string, or as an object
+/// from some servers.
+fn arguments(value: &Value) -> Value {
+    match value {
+        Value::String(s) if s.trim().is_empty() => Value::Object(Map::new()),
+        Value::String(s) => serde_json::from_str(s).unwrap_or(Value::Null),
+        other => other.clone(),
+    }
+}
+
+fn usage(provider: &str, usage: Option<&Value>) -> TokenUsage {
+    let Some(usage) = usage else {
+        return TokenUsage::empty();
+    };
+    let count = |value: &Value| value.as_u64().map(|v| u32::try_from(v).unwrap_or(u32::MAX));
+    let mut completion = count(&usage["completion_tokens"]);
+    // Grok leaves reasoning tokens out of `completion_tokens`.
+    if provider == "Grok" {
+        let reasoning = count(&usage["completion_tokens_details"]["reasoning_tokens"]).unwrap_or(0);
+        completion = completion.map(|c| c.saturating_add(reasoning));
+    }
+    TokenUsage::partial(count(&usage["prompt_tokens"]), completion, count(&usage["total_tokens"]))
+}
+
+fn response_format_value(format: ResponseFormat) -> Value {
+    match format {
+        ResponseFormat::Text => json!({ "type": "text" }),
+        ResponseFormat::JsonObject => json!({ "type": "json_object" }),
+        ResponseFormat::JsonSchema { name, schema, strict } => json!({
+            "type": "json_schema",
+            "json_schema": { "name": name, "schema": schema, "strict": strict.unwrap_or(false) },
+        }),
+    }
+}
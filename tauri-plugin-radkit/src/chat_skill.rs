@@ -1,22 +1,389 @@
+use std::collections::HashMap;
 use std::sync::Arc;
-use radkit::agent::{SkillHandler, RegisteredSkill, SkillMetadata, OnRequestResult, OnInputResult};
+use radkit::agent::{SkillHandler, RegisteredSkill, SkillMetadata, SkillSlot, OnRequestResult, OnInputResult};
 use radkit::runtime::context::{State, ProgressSender};
 use radkit::runtime::AgentRuntime;
-use radkit::models::{Content, Thread};
-use radkit::tools::BaseToolset;
+use radkit::models::{Content, ContentPart, Data, DataSource, Event, Thread};
+use radkit::tools::{BaseTool, BaseToolset, DefaultExecutionState, ExecutionState, FunctionDeclaration, ToolCall, ToolContext, ToolResponse, ToolResult};
 use radkit::errors::{AgentError};
 use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use crate::llm_factory::{DynamicLlm, RequestLlm};
+use crate::models::ChatOptions;
+use crate::task_store::CancelledTasks;
+
+/// Upper bound on LLM round-trips per request, so a model that keeps calling
+/// tools cannot loop forever.
+const MAX_TOOL_ROUNDS: usize = 16;
+
+/// Session-scoped state key holding the conversation so far, so follow-up
+/// messages in the same context see earlier turns.
+const HISTORY_KEY: &str = "chat_history";
+
+/// Key of the data part carrying a message's `ChatOptions`.
+pub const CHAT_OPTIONS_KEY: &str = "chatOptions";
+
+/// Key of the data part carrying the error of a failed task, as encoded by
+/// `Error::to_failure`.
+pub const FAILURE_KEY: &str = "error";
+
+/// Task-scoped state key holding the paused conversation while the task
+/// waits for user input.
+const PENDING_INPUT_KEY: &str = "pending_input";
+
+/// Execution-state key a tool sets, with the question to show the user, to
+/// pause the task until the user replies.
+pub const INPUT_REQUIRED_KEY: &str = "input_required";
+
+/// Execution-state keys identifying the A2A task and context a tool call
+/// belongs to.
+pub const TASK_ID_KEY: &str = "task_id";
+pub const CONTEXT_ID_KEY: &str = "context_id";
+
+/// Execution-state key holding the LLM's ID for the tool call being run.
+pub const TOOL_CALL_ID_KEY: &str = "tool_call_id";
+
+/// `type` of the data part in a status update carrying a piece of the
+/// model's reply. The task store leaves these out of the history.
+pub const TEXT_DELTA_TYPE: &str = "textDelta";
+
+/// Name of the built-in tool the model calls to ask a clarifying question.
+pub const ASK_USER_TOOL: &str = "ask_user";
+
+/// A conversation paused on a tool call that is waiting for the user's reply.
+#[derive(Serialize, Deserialize)]
+struct PendingInput {
+    events: Vec<Event>,
+    call_id: String,
+}
+
+enum Outcome {
+    Completed(Content),
+    InputRequired(Content),
+    /// The task was cancelled while running; the task store has already
+    /// recorded it as `canceled`.
+    Cancelled,
+}
 
 pub struct ChatSkill {
     tools: Arc<dyn BaseToolset>,
+    system_prompt: Option<String>,
+    llm: DynamicLlm,
+    cancelled: CancelledTasks,
 }
 
 impl ChatSkill {
-    pub fn new(tools: Arc<dyn BaseToolset>) -> Self {
-        Self { tools }
+    pub fn new(
+        tools: Arc<dyn BaseToolset>,
+        system_prompt: Option<String>,
+        llm: DynamicLlm,
+        cancelled: CancelledTasks,
+    ) -> Self {
+        Self { tools, system_prompt, llm, cancelled }
+    }
+
+    fn is_cancelled(&self, execution_state: &DefaultExecutionState) -> bool {
+        execution_state
+            .get_state(TASK_ID_KEY)
+            .and_then(|id| id.as_str().map(|id| self.cancelled.lock().unwrap().contains(id)))
+            .unwrap_or(false)
+    }
+
+    /// The tools offered for one request: all of them, or only those named
+    /// in `options.tools`.
+    fn toolset(&self, options: &ChatOptions) -> Arc<dyn BaseToolset> {
+        match &options.tools {
+            Some(names) => Arc::new(FilteredToolset {
+                inner: self.tools.clone(),
+                names: names.clone(),
+            }),
+            None => self.tools.clone(),
+        }
+    }
+
+    /// Builds the thread sent to the LLM: the rendered system prompt, if any,
+    /// followed by the conversation so far.
+    fn thread(&self, events: &[Event], options: &ChatOptions) -> Thread {
+        let mut thread_events = Vec::with_capacity(events.len() + 1);
+        if let Some(prompt) = &self.system_prompt {
+            let variables = options.variables.clone().unwrap_or_default();
+            thread_events.push(Event::system(render_template(prompt, &variables)));
+        }
+        thread_events.extend_from_slice(events);
+        Thread::new(thread_events)
+    }
+
+    async fn run_tool(&self, tools: &Arc<dyn BaseToolset>, call: &ToolCall, context: &ToolContext<'_>) -> ToolResult {
+        let tools = tools.get_tools().await;
+        let Some(tool) = tools.iter().find(|t| t.name() == call.name()) else {
+            return ToolResult::error(format!("Unknown tool: {}", call.name()));
+        };
+
+        let args: HashMap<String, Value> = match call.arguments() {
+            Value::Object(map) => map.clone().into_iter().collect(),
+            _ => HashMap::new(),
+        };
+        tool.run_async(args, context).await
+    }
+
+    /// Runs the LLM/tool loop until the model answers or a tool asks for
+    /// user input, persisting the conversation either way.
+    async fn converse(
+        &self,
+        state: &mut State,
+        progress: &ProgressSender,
+        mut events: Vec<Event>,
+        options: &ChatOptions,
+    ) -> Result<Outcome, AgentError> {
+        let llm = self.llm.for_request(options).map_err(|e| AgentError::Internal {
+            component: "ChatSkill".into(),
+            reason: e.to_string(),
+        })?;
+        let tools = self.toolset(options);
+        let execution_state = DefaultExecutionState::new();
+        // Seeded into the session by `IdentifiedTaskStore`.
+        for key in [TASK_ID_KEY, CONTEXT_ID_KEY] {
+            if let Some(id) = state.session_ref().load::<String>(key)? {
+                execution_state.set_state(key, Value::String(id));
+            }
+        }
+        let tool_context = ToolContext::new(&execution_state);
+
+        for _ in 0..MAX_TOOL_ROUNDS {
+            if self.is_cancelled(&execution_state) {
+                return Ok(Outcome::Cancelled);
+            }
+            let thread = self.thread(&events, options);
+            let message = generate(&llm, thread, &tools, progress).await?;
+            let calls: Vec<ToolCall> = message.tool_calls().into_iter().cloned().collect();
+
+            events.push(Event::assistant(message.clone()));
+            if calls.is_empty() {
+                state.session().save(HISTORY_KEY, &events)?;
+                return Ok(Outcome::Completed(message));
+            }
+
+            let mut paused = None;
+            for call in &calls {
+                execution_state.set_state(TOOL_CALL_ID_KEY, Value::String(call.id().to_string()));
+                let reported = call.name() != ASK_USER_TOOL;
+                if reported {
+                    send_tool_progress(progress, call, "calling", None).await?;
+                }
+                let result = self.run_tool(&tools, call, &tool_context).await;
+                if reported {
+                    match result.error_message() {
+                        Some(error) => send_tool_progress(progress, call, "failed", Some(error)).await?,
+                        None => send_tool_progress(progress, call, "returned", None).await?,
+                    }
+                }
+                if let Some(question) = execution_state.get_state(INPUT_REQUIRED_KEY).filter(|q| !q.is_null()) {
+                    execution_state.set_state(INPUT_REQUIRED_KEY, Value::Null);
+                    if paused.is_none() {
+                        // The user's reply becomes this call's result.
+                        paused = Some((call.id().to_string(), question));
+                        continue;
+                    }
+                }
+                events.push(Event::from(ToolResponse::new(call.id(), result)));
+                if self.is_cancelled(&execution_state) {
+                    return Ok(Outcome::Cancelled);
+                }
+            }
+
+            if let Some((call_id, question)) = paused {
+                let question = question.as_str().map(str::to_string).unwrap_or_else(|| question.to_string());
+                state.task().save(PENDING_INPUT_KEY, &PendingInput { events, call_id })?;
+                return Ok(Outcome::InputRequired(Content::from_text(question)));
+            }
+        }
+
+        Err(AgentError::Internal {
+            component: "ChatSkill".into(),
+            reason: format!("Exceeded {} tool rounds without a final answer", MAX_TOOL_ROUNDS),
+        })
+    }
+}
+
+/// Calls the LLM, forwarding the reply's text to the task's stream as
+/// [`TEXT_DELTA_TYPE`] status updates while it is generated.
+async fn generate(
+    llm: &RequestLlm,
+    thread: Thread,
+    tools: &Arc<dyn BaseToolset>,
+    progress: &ProgressSender,
+) -> Result<Content, AgentError> {
+    let (deltas, mut received) = mpsc::unbounded_channel::<String>();
+    let forward = async {
+        while let Some(mut text) = received.recv().await {
+            // Send whatever arrived since the last update as one.
+            while let Ok(more) = received.try_recv() {
+                text.push_str(&more);
+            }
+            let data = serde_json::json!({ "type": TEXT_DELTA_TYPE, "text": text });
+            progress.send_update(Content::from_parts(vec![json_part(&data)])).await?;
+        }
+        Ok::<_, AgentError>(())
+    };
+    let generation = async move { llm.generate(thread, Some(tools.clone()), &deltas).await };
+    let (response, forwarded) = tokio::join!(generation, forward);
+    forwarded?;
+    Ok(response?.content().clone())
+}
+
+/// Offers only the named tools of `inner`. Names that match no tool are
+/// ignored.
+struct FilteredToolset {
+    inner: Arc<dyn BaseToolset>,
+    names: Vec<String>,
+}
+
+#[async_trait]
+impl BaseToolset for FilteredToolset {
+    async fn get_tools(&self) -> Vec<&dyn BaseTool> {
+        self.inner
+            .get_tools()
+            .await
+            .into_iter()
+            .filter(|tool| self.names.iter().any(|name| name == tool.name()))
+            .collect()
+    }
+
+    async fn close(&self) {
+        self.inner.close().await
+    }
+}
+
+/// Lets the model pause the task and ask the user a clarifying question.
+pub struct AskUserTool;
+
+#[async_trait]
+impl BaseTool for AskUserTool {
+    fn name(&self) -> &str {
+        ASK_USER_TOOL
+    }
+
+    fn description(&self) -> &str {
+        "Ask the user a clarifying question and wait for their answer before continuing."
+    }
+
+    fn declaration(&self) -> FunctionDeclaration {
+        FunctionDeclaration::new(
+            ASK_USER_TOOL.to_string(),
+            self.description().to_string(),
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "question": { "type": "string", "description": "The question to ask the user" }
+                },
+                "required": ["question"]
+            }),
+        )
+    }
+
+    async fn run_async(
+        &self,
+        args: HashMap<String, Value>,
+        context: &ToolContext<'_>,
+    ) -> ToolResult {
+        let question = args
+            .get("question")
+            .cloned()
+            .unwrap_or_else(|| Value::String("Please provide more details.".into()));
+        context.state().set_state(INPUT_REQUIRED_KEY, question);
+        ToolResult::success(Value::Null)
     }
 }
 
+/// Reports a step of a tool call to the task's stream and history as a
+/// data-only status update, so it is not mistaken for reply text.
+async fn send_tool_progress(
+    progress: &ProgressSender,
+    call: &ToolCall,
+    phase: &str,
+    error: Option<&str>,
+) -> Result<(), AgentError> {
+    let mut data = serde_json::json!({
+        "type": "toolCall",
+        "phase": phase,
+        "name": call.name(),
+        "toolCallId": call.id(),
+    });
+    if phase == "calling" {
+        data["args"] = call.arguments().clone();
+    }
+    if let Some(error) = error {
+        data["error"] = Value::String(error.to_string());
+    }
+    progress.send_update(Content::from_parts(vec![json_part(&data)])).await
+}
+
+/// Encodes a JSON value the way radkit represents A2A data parts.
+fn json_part(value: &Value) -> ContentPart {
+    let encoded = STANDARD.encode(serde_json::to_vec(value).unwrap_or_default());
+    ContentPart::Data(Data::new_unchecked("application/json", DataSource::Base64(encoded), None))
+}
+
+/// Decodes a JSON data part, the form A2A data parts reach a skill in.
+fn json_value(part: &ContentPart) -> Option<Value> {
+    let data = part.as_data()?;
+    match (data.content_type.as_str(), &data.source) {
+        ("application/json", DataSource::Base64(encoded)) => {
+            serde_json::from_slice(&STANDARD.decode(encoded).ok()?).ok()
+        }
+        _ => None,
+    }
+}
+
+/// The message of a failed task: the error as text for display, and its
+/// structured form under [`FAILURE_KEY`] so the commands can rebuild it.
+fn failure_content(error: AgentError) -> Content {
+    let error = crate::Error::from(error);
+    Content::from_parts(vec![
+        ContentPart::Text(error.to_string()),
+        json_part(&serde_json::json!({ FAILURE_KEY: error.to_failure() })),
+    ])
+}
+
+/// Reads the `ChatOptions` the commands attach as a data part keyed by
+/// [`CHAT_OPTIONS_KEY`]. radkit does not hand message metadata to skills.
+fn request_options(content: &Content) -> ChatOptions {
+    content
+        .parts()
+        .iter()
+        .filter_map(json_value)
+        .find_map(|value| value.get(CHAT_OPTIONS_KEY).cloned())
+        .and_then(|options| serde_json::from_value(options).ok())
+        .unwrap_or_default()
+}
+
+/// Replaces `{{name}}` placeholders with their values. Unknown placeholders
+/// are left as-is so a missing variable is visible in the prompt. The
+/// template is scanned once, so placeholders inside values stay literal.
+fn render_template(template: &str, variables: &HashMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        match variables.get(&after[..end]) {
+            Some(value) => rendered.push_str(value),
+            None => rendered.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
 static CHAT_METADATA: SkillMetadata = SkillMetadata::new(
     "chat",
     "Chat",
@@ -31,35 +398,71 @@ static CHAT_METADATA: SkillMetadata = SkillMetadata::new(
 impl SkillHandler for ChatSkill {
     async fn on_request(
         &self,
-        _state: &mut State,
-        _progress: &ProgressSender,
-        runtime: &dyn AgentRuntime,
+        state: &mut State,
+        progress: &ProgressSender,
+        _runtime: &dyn AgentRuntime,
         content: Content,
     ) -> Result<OnRequestResult, AgentError> {
-        let llm = runtime.default_llm();
+        let options = request_options(&content);
 
         // We use the text content for now.
         let text = content.joined_texts().unwrap_or_default();
-        let thread = Thread::from_user(text);
+        let mut events: Vec<Event> = state.session_ref().load(HISTORY_KEY)?.unwrap_or_default();
+        events.push(Event::user(text));
 
-        let response = llm.generate_content(thread, Some(self.tools.clone())).await?;
-
-        Ok(OnRequestResult::Completed {
-            message: Some(response.content().clone()),
-            artifacts: Vec::new(),
+        Ok(match self.converse(state, progress, events, &options).await {
+            Ok(Outcome::Completed(message)) => OnRequestResult::Completed {
+                message: Some(message),
+                artifacts: Vec::new(),
+            },
+            Ok(Outcome::InputRequired(message)) => OnRequestResult::InputRequired {
+                message,
+                slot: SkillSlot::new(ASK_USER_TOOL),
+            },
+            Ok(Outcome::Cancelled) => OnRequestResult::Completed {
+                message: None,
+                artifacts: Vec::new(),
+            },
+            Err(error) => OnRequestResult::Failed {
+                error: failure_content(error),
+            },
         })
     }
 
     async fn on_input_received(
         &self,
-        _state: &mut State,
-        _progress: &ProgressSender,
+        state: &mut State,
+        progress: &ProgressSender,
         _runtime: &dyn AgentRuntime,
-        _input: Content,
+        input: Content,
     ) -> Result<OnInputResult, AgentError> {
-        Err(AgentError::Internal {
+        let pending: PendingInput = state.task().load(PENDING_INPUT_KEY)?.ok_or_else(|| AgentError::Internal {
             component: "ChatSkill".into(),
-            reason: "Unexpected input received".into(),
+            reason: "Input received but the task is not waiting for any".into(),
+        })?;
+        state.task().remove(PENDING_INPUT_KEY);
+
+        let options = request_options(&input);
+        let reply = serde_json::json!({ "userInput": input.joined_texts().unwrap_or_default() });
+        let mut events = pending.events;
+        events.push(Event::from(ToolResponse::new(pending.call_id, ToolResult::success(reply))));
+
+        Ok(match self.converse(state, progress, events, &options).await {
+            Ok(Outcome::Completed(message)) => OnInputResult::Completed {
+                message: Some(message),
+                artifacts: Vec::new(),
+            },
+            Ok(Outcome::InputRequired(message)) => OnInputResult::InputRequired {
+                message,
+                slot: SkillSlot::new(ASK_USER_TOOL),
+            },
+            Ok(Outcome::Cancelled) => OnInputResult::Completed {
+                message: None,
+                artifacts: Vec::new(),
+            },
+            Err(error) => OnInputResult::Failed {
+                error: failure_content(error),
+            },
         })
     }
 }
@@ -69,3 +472,24 @@ impl RegisteredSkill for ChatSkill {
         &CHAT_METADATA
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn render_template_fills_known_and_keeps_unknown_placeholders() {
+        let rendered = render_template("Hi {{name}}, {{missing}} {{name}}{{", &variables(&[("name", "Ada")]));
+        assert_eq!(rendered, "Hi Ada, {{missing}} Ada{{");
+    }
+
+    #[test]
+    fn render_template_does_not_expand_placeholders_in_values() {
+        let vars = variables(&[("a", "{{b}}"), ("b", "{{a}}")]);
+        assert_eq!(render_template("{{a}} and {{b}}", &vars), "{{b}} and {{a}}");
+    }
+}
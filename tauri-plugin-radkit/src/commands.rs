@@ -1,52 +1,159 @@
-use tauri::{AppHandle, State, Runtime as TauriRuntime, Emitter};
+use tauri::{AppHandle, State, Runtime as TauriRuntime, Emitter, Webview};
+use tauri::ipc::JavaScriptChannelId;
 use crate::models::*;
 use crate::runtime_holder::RadkitRuntimeState;
-use crate::frontend_tool::FrontendTool;
-use crate::chat_skill::ChatSkill;
+use crate::{Error, Result};
+use crate::frontend_tool::{cancel_task_requests, FrontendTool};
+use crate::chat_skill::{AskUserTool, CHAT_OPTIONS_KEY, ChatSkill, FAILURE_KEY};
+use crate::backend_tool::BackendTool;
+use crate::llm_factory::{build_llm, DynamicLlm, LlmSlot};
+use crate::cassette::Cassette;
+use crate::task_store::{CancelledTasks, FileTaskStore, IdentifiedTaskStore};
+use crate::memory_store::{Embedder, FileMemoryService};
+use crate::streams::{ActiveStream, StreamMessage, StreamSink};
 
 use radkit::agent::Agent;
-use radkit::runtime::{Runtime, RuntimeBuilder, AgentRuntime};
-use radkit::models::providers::{
-    OpenAILlm, AnthropicLlm, GeminiLlm, OpenRouterLlm, GrokLlm, DeepSeekLlm
-};
-use radkit::tools::{BaseTool, SimpleToolset, ToolResult};
+use radkit::runtime::{AgentRuntime, InMemoryTaskStore, Runtime, RuntimeBuilder, TaskEvent, TaskStore};
+use radkit::runtime::task_manager::Task;
+use radkit::tools::{BaseTool, SimpleToolset};
 use radkit::runtime::memory::{MemoryContent, SearchOptions};
 use radkit::runtime::context::AuthContext;
 
 use a2a_types::{
-    MessageSendParams, Message, MessageRole, Part, TaskQueryParams, TaskIdParams
+    MessageSendParams, Message, MessageRole, Part, SendMessageResponse, TaskQueryParams
 };
 use a2a_client::A2AClient;
 
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use futures::StreamExt;
+use tokio::sync::watch;
 use serde_json::Value;
 
-struct DynamicLlm(Arc<dyn radkit::models::BaseLlm>);
+fn get_client(state: &RadkitRuntimeState) -> Result<A2AClient> {
+    let guard = state.client.lock().unwrap();
+    guard.clone().ok_or_else(|| state.not_started())
+}
+
+fn get_runtime(state: &RadkitRuntimeState) -> Result<Arc<Runtime>> {
+    let guard = state.runtime.lock().unwrap();
+    guard.clone().ok_or_else(|| state.not_started())
+}
+
+fn get_tasks(state: &RadkitRuntimeState) -> Result<IdentifiedTaskStore> {
+    let guard = state.tasks.lock().unwrap();
+    guard.clone().ok_or_else(|| state.not_started())
+}
+
+/// How long `stop_stream` waits for a stream that has not reported its task
+/// yet, so the task can still be cancelled.
+const STOP_TASK_WAIT: Duration = Duration::from_secs(5);
+
+fn user_message(
+    text: String,
+    context_id: Option<String>,
+    task_id: Option<String>,
+    options: Option<ChatOptions>,
+) -> Result<MessageSendParams> {
+    let mut parts = vec![Part::Text { text, metadata: None }];
+    if let Some(options) = options {
+        parts.push(Part::Data {
+            data: serde_json::json!({ CHAT_OPTIONS_KEY: options }),
+            metadata: None,
+        });
+    }
+
+    Ok(MessageSendParams {
+        message: Message {
+            kind: "message".into(),
+            message_id: uuid::Uuid::new_v4().to_string(),
+            role: MessageRole::User,
+            parts,
+            context_id,
+            task_id,
+            reference_task_ids: vec![],
+            extensions: vec![],
+            metadata: None,
+        },
+        configuration: None,
+        metadata: None,
+    })
+}
+
+/// The task a `chat` result or streamed event belongs to.
+fn event_task_id(value: &Value) -> Option<String> {
+    let id = if value["kind"] == "task" { &value["id"] } else { &value["taskId"] };
+    id.as_str().map(str::to_string)
+}
 
-#[async_trait::async_trait]
-impl radkit::models::BaseLlm for DynamicLlm {
-    fn model_name(&self) -> &str {
-        self.0.model_name()
+/// The error `ChatSkill` attached to a failure message, if `message` is one.
+fn message_failure(message: &Value) -> Option<Error> {
+    message["parts"]
+        .as_array()?
+        .iter()
+        .find(|part| part["kind"] == "data" && part["data"].get(FAILURE_KEY).is_some())
+        .map(|part| Error::from_failure(&part["data"][FAILURE_KEY]))
+}
+
+/// The error for a failed task that carries no failure message, e.g. one
+/// run by a skill other than `ChatSkill`.
+fn task_failed(value: &Value) -> Error {
+    Error::Agent {
+        component: "task".into(),
+        reason: format!("Task {} failed", event_task_id(value).unwrap_or_default()),
     }
-    async fn generate_content(
-        &self,
-        thread: radkit::models::Thread,
-        toolset: Option<Arc<dyn radkit::tools::BaseToolset>>,
-    ) -> radkit::errors::AgentResult<radkit::models::LlmResponse> {
-        self.0.generate_content(thread, toolset).await
+}
+
+/// Emits `task_input_required` when a task, or a status update for one, shows
+/// it waiting for the user's reply.
+fn notify_input_required<R: TauriRuntime>(app: &AppHandle<R>, value: &Value) {
+    let status = &value["status"];
+    if status["state"] != "input-required" {
+        return;
     }
+    let _ = app.emit("task_input_required", serde_json::json!({
+        "taskId": event_task_id(value),
+        "contextId": value["contextId"],
+        "message": status["message"],
+    }));
 }
 
-fn get_client(state: &State<'_, RadkitRuntimeState>) -> Result<A2AClient, String> {
-    let guard = state.client.lock().unwrap();
-    guard.clone().ok_or_else(|| "Client not initialized".to_string())
+/// The task store on the journal at `path`: the one already open, or a newly
+/// opened one.
+async fn open_task_file(state: &RadkitRuntimeState, path: PathBuf) -> Result<FileTaskStore> {
+    let mut open = state.task_files.lock().await;
+    if let Some(store) = open.get(&path) {
+        return Ok(store.clone());
+    }
+    let store = FileTaskStore::open(path.clone()).await?;
+    open.insert(path, store.clone());
+    Ok(store)
 }
 
-fn get_runtime(state: &State<'_, RadkitRuntimeState>) -> Result<Arc<Runtime>, String> {
-    let guard = state.runtime.lock().unwrap();
-    guard.clone().ok_or_else(|| "Runtime not initialized".to_string())
+/// The memory service on the file at `path`, switched to `embedder`: the
+/// one already open, or a newly opened one.
+async fn open_memory_file(state: &RadkitRuntimeState, path: PathBuf, embedder: Embedder) -> Result<FileMemoryService> {
+    let mut open = state.memory_files.lock().await;
+    if let Some(service) = open.get(&path) {
+        service.set_embedder(embedder).await?;
+        return Ok(service.clone());
+    }
+    let service = FileMemoryService::open(path.clone(), embedder).await?;
+    open.insert(path, service.clone());
+    Ok(service)
+}
+
+/// Rejects an `LlmConfig` from the frontend that would send a stored key to
+/// a server of the frontend's choosing. The host app can still combine them
+/// through `Radkit`.
+fn check_frontend_llm(config: &LlmConfig) -> Result<()> {
+    if let LlmConfig::OpenAICompatible { credential: Some(_), .. } = config {
+        return Err(Error::ProviderConfig(
+            "OpenAICompatible cannot use a stored credential from the frontend; pass apiKey instead".into(),
+        ));
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -54,93 +161,79 @@ pub async fn init_agent<R: TauriRuntime>(
     app: AppHandle<R>,
     state: State<'_, RadkitRuntimeState>,
     config: InitAgentRequest,
-) -> Result<InitResponse, String> {
-    let llm_arc: Arc<dyn radkit::models::BaseLlm> = match config.llm {
-        LlmConfig::OpenAI { model, api_key, common } => {
-            let mut llm = if let Some(key) = api_key {
-                OpenAILlm::new(model, key)
-            } else {
-                OpenAILlm::from_env(model).map_err(|e| e.to_string())?
-            };
-            if let Some(c) = common {
-                if let Some(mt) = c.max_tokens { llm = llm.with_max_tokens(mt); }
-                if let Some(temp) = c.temperature { llm = llm.with_temperature(temp); }
-            }
-            Arc::new(llm)
-        },
-        LlmConfig::Anthropic { model, api_key, common } => {
-             let mut llm = if let Some(key) = api_key {
-                AnthropicLlm::new(model, key)
-            } else {
-                AnthropicLlm::from_env(model).map_err(|e| e.to_string())?
-            };
-            if let Some(c) = common {
-                if let Some(mt) = c.max_tokens { llm = llm.with_max_tokens(mt); }
-                if let Some(temp) = c.temperature { llm = llm.with_temperature(temp); }
-            }
-            Arc::new(llm)
-        },
-        LlmConfig::Gemini { model, api_key, common } => {
-             let mut llm = if let Some(key) = api_key {
-                GeminiLlm::new(model, key)
-            } else {
-                GeminiLlm::from_env(model).map_err(|e| e.to_string())?
-            };
-             if let Some(c) = common {
-                if let Some(mt) = c.max_tokens { llm = llm.with_max_tokens(mt); }
-                if let Some(temp) = c.temperature { llm = llm.with_temperature(temp); }
-            }
-            Arc::new(llm)
-        },
-        LlmConfig::OpenRouter { model, api_key, site_url, app_name, common } => {
-             let mut llm = if let Some(key) = api_key {
-                OpenRouterLlm::new(model, key)
-            } else {
-                OpenRouterLlm::from_env(model).map_err(|e| e.to_string())?
-            };
-            if let Some(url) = site_url { llm = llm.with_site_url(url); }
-            if let Some(name) = app_name { llm = llm.with_app_name(name); }
+) -> Result<InitResponse> {
+    if !state.config.allow_frontend_init {
+        return Err(Error::InvalidRequest("The agent is configured by the host app".into()));
+    }
+    check_frontend_llm(&config.llm)?;
+    start_agent(&app, &state, config).await
+}
 
-             if let Some(c) = common {
-                if let Some(mt) = c.max_tokens { llm = llm.with_max_tokens(mt); }
-                if let Some(temp) = c.temperature { llm = llm.with_temperature(temp); }
-            }
-            Arc::new(llm)
-        },
-        LlmConfig::Grok { model, api_key, common } => {
-             let mut llm = if let Some(key) = api_key {
-                GrokLlm::new(model, key)
-            } else {
-                GrokLlm::from_env(model).map_err(|e| e.to_string())?
-            };
-             if let Some(c) = common {
-                if let Some(mt) = c.max_tokens { llm = llm.with_max_tokens(mt); }
-                if let Some(temp) = c.temperature { llm = llm.with_temperature(temp); }
-            }
-            Arc::new(llm)
-        },
-         LlmConfig::DeepSeek { model, api_key, common } => {
-             let mut llm = if let Some(key) = api_key {
-                DeepSeekLlm::new(model, key)
-            } else {
-                DeepSeekLlm::from_env(model).map_err(|e| e.to_string())?
-            };
-             if let Some(c) = common {
-                if let Some(mt) = c.max_tokens { llm = llm.with_max_tokens(mt); }
-                if let Some(temp) = c.temperature { llm = llm.with_temperature(temp); }
-            }
-            Arc::new(llm)
-        },
-    };
+/// Checks that `config` could start an agent: its key is found and every
+/// option is supported. The provider is not contacted.
+#[tauri::command]
+pub async fn validate_llm_config(
+    state: State<'_, RadkitRuntimeState>,
+    config: LlmConfig,
+) -> Result<()> {
+    check_frontend_llm(&config)?;
+    build_llm(config, &state.secrets).map(|_| ())
+}
+
+/// Replaces the running agent's LLM without restarting it, keeping its tasks,
+/// tools and server. Requests already in flight finish on the previous LLM.
+#[tauri::command]
+pub async fn update_llm(
+    state: State<'_, RadkitRuntimeState>,
+    config: LlmConfig,
+) -> Result<()> {
+    if !state.config.allow_frontend_init {
+        return Err(Error::InvalidRequest("The agent is configured by the host app".into()));
+    }
+    check_frontend_llm(&config)?;
+    swap_llm(&state, config)
+}
+
+pub(crate) fn swap_llm(state: &RadkitRuntimeState, config: LlmConfig) -> Result<()> {
+    let slot = state.llm.lock().unwrap().clone().ok_or_else(|| state.not_started())?;
+    slot.swap(config, &state.secrets)
+}
 
-    let llm = DynamicLlm(llm_arc);
+/// Builds the agent described by `config`, serves it and connects the A2A
+/// client, replacing any agent started before.
+pub(crate) async fn start_agent<R: TauriRuntime>(
+    app: &AppHandle<R>,
+    state: &RadkitRuntimeState,
+    config: InitAgentRequest,
+) -> Result<InitResponse> {
+    let slot = Arc::new(LlmSlot::new(config.llm, &state.secrets)?);
+
+    let cassette = match config.cassette {
+        Some(c) => Some(Arc::new(Cassette::open(c.path, c.mode)?)),
+        None => None,
+    };
+    let llm = DynamicLlm { slot: slot.clone(), cassette };
 
     let mut tools: Vec<Box<dyn BaseTool>> = Vec::new();
+    if config.ask_user {
+        tools.push(Box::new(AskUserTool));
+    }
+    for tool in &state.config.tools {
+        tools.push(Box::new(BackendTool(tool.clone())));
+    }
     for tool_def in config.tools {
+        if tools.iter().any(|t| t.name() == tool_def.name) {
+            return Err(Error::InvalidRequest(format!("Tool {} is already registered", tool_def.name)));
+        }
+        let timeout = match tool_def.timeout_ms.or(config.tool_timeout_ms).unwrap_or(DEFAULT_TOOL_TIMEOUT_MS) {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        };
         let tool = FrontendTool::new(
             tool_def.name,
             tool_def.description,
             tool_def.parameters,
+            timeout,
             app.clone(),
             state.tool_requests.clone(),
         );
@@ -148,32 +241,69 @@ pub async fn init_agent<R: TauriRuntime>(
     }
 
     let toolset = Arc::new(SimpleToolset::new(tools));
-    let chat_skill = ChatSkill::new(toolset);
+    let cancelled = CancelledTasks::default();
+    let chat_skill = ChatSkill::new(toolset, config.system_prompt, llm.clone(), cancelled.clone());
 
-    let agent = Agent::builder()
+    let mut agent = Agent::builder()
         .with_name(config.name)
         .with_description(config.description)
-        .with_skill(chat_skill)
-        .build();
-
-    let runtime = RuntimeBuilder::new(agent, llm).build();
+        .with_skill(chat_skill);
+    for add_skill in &state.config.skills {
+        agent = add_skill(agent);
+    }
+    let agent = agent.build();
+
+    let mut builder = RuntimeBuilder::new(agent, llm).with_auth_service(state.auth_service());
+    let task_store = match config.task_store.unwrap_or_default() {
+        TaskStoreConfig::Memory => IdentifiedTaskStore::new(InMemoryTaskStore::new(), cancelled),
+        TaskStoreConfig::File { path } => {
+            let path = match path {
+                Some(path) => PathBuf::from(path),
+                None => state.config.data_dir(app)?.join("tasks.jsonl"),
+            };
+            IdentifiedTaskStore::new(open_task_file(state, path).await?, cancelled)
+        }
+    };
+    builder = builder.with_task_store(task_store.clone());
+    if let MemoryStoreConfig::File { path, embedding } = config.memory.unwrap_or_default() {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => state.config.data_dir(app)?.join("memory.json"),
+        };
+        let embedder = Embedder::from_config(embedding.unwrap_or(EmbeddingConfig::Hashed { dimensions: None }), &state.secrets)?;
+        builder = builder.with_memory_service(open_memory_file(state, path, embedder).await?);
+    }
+    let runtime = builder.build();
+
+    // Stop the previous agent's server first, so a fixed bind address is
+    // free again.
+    let previous = state.server.lock().unwrap().take();
+    if let Some(server) = previous {
+        *state.client.lock().unwrap() = None;
+        server.abort();
+        let _ = server.await;
+    }
 
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.map_err(|e| e.to_string())?;
-    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let listener = tokio::net::TcpListener::bind(&state.config.bind_address)
+        .await
+        .map_err(|e| Error::ServerStart(format!("Cannot bind {}: {}", state.config.bind_address, e)))?;
+    let addr = listener.local_addr()?.to_string();
     drop(listener);
 
-    let addr = format!("127.0.0.1:{}", port);
     let base_url = format!("http://{}", addr);
 
     let runtime_clone = runtime.clone();
 
     *state.runtime.lock().unwrap() = Some(Arc::new(runtime));
+    *state.tasks.lock().unwrap() = Some(task_store);
+    *state.llm.lock().unwrap() = Some(slot);
 
-    tauri::async_runtime::spawn(async move {
+    let server = tauri::async_runtime::spawn(async move {
         if let Err(e) = runtime_clone.serve(&addr).await {
             eprintln!("Radkit server error: {}", e);
         }
     });
+    *state.server.lock().unwrap() = Some(server);
 
     let client = reqwest::Client::new();
     let card_url = format!("{}/.well-known/agent-card.json", base_url);
@@ -183,123 +313,316 @@ pub async fn init_agent<R: TauriRuntime>(
              break;
         }
         if retries > 50 {
-            return Err("Failed to start agent server".into());
+            return Err(Error::ServerStart(format!("{} did not respond", card_url)));
         }
         tokio::time::sleep(Duration::from_millis(200)).await;
         retries += 1;
     }
 
-    let a2a_client = A2AClient::from_card_url(&base_url).await.map_err(|e| e.to_string())?;
+    let a2a_client = A2AClient::from_card_url(&base_url).await.map_err(|e| Error::a2a(e, None))?;
     *state.client.lock().unwrap() = Some(a2a_client);
 
     Ok(InitResponse { success: true })
 }
 
 #[tauri::command]
-pub async fn chat(
+pub async fn chat<R: TauriRuntime>(
+    app: AppHandle<R>,
     state: State<'_, RadkitRuntimeState>,
     message: String,
     context_id: Option<String>,
     task_id: Option<String>,
-) -> Result<serde_json::Value, String> {
+    options: Option<ChatOptions>,
+) -> Result<serde_json::Value> {
     let client = get_client(&state)?;
+    let params = user_message(message, context_id, task_id.clone(), options)?;
 
-    let params = MessageSendParams {
-        message: Message {
-            kind: "message".into(),
-            message_id: uuid::Uuid::new_v4().to_string(),
-            role: MessageRole::User,
-            parts: vec![Part::Text { text: message, metadata: None }],
-            context_id: context_id.clone(),
-            task_id: task_id.clone(),
-            reference_task_ids: vec![],
-            extensions: vec![],
-            metadata: None,
-        },
-        configuration: None,
-        metadata: None,
+    let response = client.send_message(params).await.map_err(|e| Error::a2a(e, task_id.as_deref()))?;
+    let result = match response {
+        SendMessageResponse::Success(success) => success.result,
+        SendMessageResponse::Error(e) => return Err(Error::jsonrpc(&e.error, task_id.as_deref())),
     };
 
-    let result = client.send_message(params).await.map_err(|e| e.to_string())?;
-
-    Ok(serde_json::to_value(result).map_err(|e| e.to_string())?)
+    let mut value = serde_json::to_value(result)?;
+    // A task cancelled while it ran comes back as the skill left it; report
+    // it as stored.
+    if let Some(id) = value["id"].as_str().filter(|_| value["kind"] == "task").map(str::to_string) {
+        let tasks = get_tasks(&state)?;
+        if tasks.is_cancelled(&id) {
+            let auth = state.auth_context();
+            if let Some(task) = tasks.get_task(&auth, &id).await? {
+                value = a2a_task(&tasks, &auth, task).await?;
+            }
+        }
+    }
+    if value["status"]["state"] == "failed" {
+        let history = value["history"].as_array().map(Vec::as_slice).unwrap_or_default();
+        return Err(history.iter().rev().find_map(message_failure).unwrap_or_else(|| task_failed(&value)));
+    }
+    notify_input_required(&app, &value);
+    Ok(value)
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn stream_chat<R: TauriRuntime>(
     app: AppHandle<R>,
     state: State<'_, RadkitRuntimeState>,
     message: String,
     context_id: Option<String>,
     task_id: Option<String>,
-) -> Result<(), String> {
-    let client = get_client(&state)?;
-
-     let params = MessageSendParams {
-        message: Message {
-            kind: "message".into(),
-            message_id: uuid::Uuid::new_v4().to_string(),
-            role: MessageRole::User,
-            parts: vec![Part::Text { text: message, metadata: None }],
-            context_id: context_id.clone(),
-            task_id: task_id.clone(),
-            reference_task_ids: vec![],
-            extensions: vec![],
-            metadata: None,
-        },
-        configuration: None,
-        metadata: None,
+    options: Option<ChatOptions>,
+    webview: Webview<R>,
+    on_event: Option<JavaScriptChannelId>,
+) -> Result<String> {
+    let sink = match on_event {
+        Some(id) => StreamSink::Channel(id.channel_on(webview)),
+        None => StreamSink::Broadcast,
     };
+    start_stream(&app, &state, message, context_id, task_id, options, sink).await
+}
+
+/// Sends a streaming message and forwards its events to `sink` until the
+/// stream ends or is stopped. Returns the stream ID.
+pub(crate) async fn start_stream<R: TauriRuntime>(
+    app: &AppHandle<R>,
+    state: &RadkitRuntimeState,
+    message: String,
+    context_id: Option<String>,
+    task_id: Option<String>,
+    options: Option<ChatOptions>,
+    sink: StreamSink,
+) -> Result<String> {
+    let client = get_client(state)?;
+    let params = user_message(message, context_id, task_id.clone(), options)?;
+
+    let mut stream = client
+        .send_streaming_message(params)
+        .await
+        .map_err(|e| Error::a2a(e, task_id.as_deref()))?;
+
+    let stream_id = uuid::Uuid::new_v4().to_string();
+    let tasks = get_tasks(state)?;
+    let (task_id_sender, task_id_receiver) = watch::channel(task_id.clone());
+
+    // Hold the lock until the entry is inserted, so a stream that ends
+    // immediately cannot remove itself before it has been registered.
+    let mut streams = state.streams.lock().unwrap();
+    let handle = {
+        let app = app.clone();
+        let streams = state.streams.clone();
+        let stream_id = stream_id.clone();
+        let mut task_id = task_id;
+        tauri::async_runtime::spawn(async move {
+            let mut context_id = None;
+            let mut task_state = None;
+            let mut started = false;
+            let mut failed = false;
+            let mut failure = None;
+
+            while let Some(event_result) = stream.next().await {
+                let event = match event_result {
+                    Ok(event) => event,
+                    Err(e) => {
+                        let error = Error::a2a(e, task_id.as_deref());
+                        sink.deliver(&app, StreamMessage::Error {
+                            stream_id: stream_id.clone(),
+                            task_id: task_id.clone(),
+                            error,
+                        }).await;
+                        failed = true;
+                        break;
+                    }
+                };
+
+                let value = serde_json::to_value(&event).unwrap_or(Value::Null);
+                if let Some(id) = event_task_id(&value).filter(|_| task_id.is_none()) {
+                    task_id = Some(id);
+                    task_id_sender.send_replace(task_id.clone());
+                }
+                if let Some(id) = value["contextId"].as_str() {
+                    context_id.get_or_insert_with(|| id.to_string());
+                }
+                if let Some(state) = value["status"]["state"].as_str() {
+                    task_state = Some(state.to_string());
+                }
+                if value["kind"] == "message" {
+                    failure = message_failure(&value).or(failure);
+                }
+                if !started {
+                    started = true;
+                    sink.deliver(&app, StreamMessage::Started {
+                        stream_id: stream_id.clone(),
+                        task_id: task_id.clone(),
+                        context_id: context_id.clone(),
+                    }).await;
+                }
+                notify_input_required(&app, &value);
+                sink.deliver(&app, StreamMessage::Event {
+                    stream_id: stream_id.clone(),
+                    event: value,
+                }).await;
+            }
 
-     let mut stream = client.send_streaming_message(params).await.map_err(|e| e.to_string())?;
-
-     let app_clone = app.clone();
-     tauri::async_runtime::spawn(async move {
-         while let Some(event_result) = stream.next().await {
-             match event_result {
-                Ok(event) => {
-                     let _ = app_clone.emit("stream_event", event);
-                },
-                Err(e) => {
-                     eprintln!("Stream error: {}", e);
+            if task_id.as_deref().is_some_and(|id| tasks.is_cancelled(id)) {
+                task_state = Some("canceled".to_string());
+            }
+            if !failed && task_state.as_deref() == Some("failed") {
+                let error = failure.unwrap_or_else(|| {
+                    task_failed(&serde_json::json!({ "kind": "task", "id": task_id }))
+                });
+                sink.deliver(&app, StreamMessage::Error {
+                    stream_id: stream_id.clone(),
+                    task_id,
+                    error,
+                }).await;
+            } else if !failed {
+                if !started {
+                    sink.deliver(&app, StreamMessage::Started {
+                        stream_id: stream_id.clone(),
+                        task_id: task_id.clone(),
+                        context_id: context_id.clone(),
+                    }).await;
                 }
-             }
-         }
-     });
-     Ok(())
+                sink.deliver(&app, StreamMessage::Completed {
+                    stream_id: stream_id.clone(),
+                    task_id,
+                    context_id,
+                    state: task_state,
+                }).await;
+            }
+            streams.lock().unwrap().remove(&stream_id);
+        })
+    };
+    streams.insert(stream_id.clone(), ActiveStream {
+        handle,
+        task_id: task_id_receiver,
+    });
+    Ok(stream_id)
+}
+
+/// Stops forwarding a stream started by `stream_chat` and cancels its task.
+#[tauri::command]
+pub async fn stop_stream<R: TauriRuntime>(
+    app: AppHandle<R>,
+    state: State<'_, RadkitRuntimeState>,
+    stream_id: String,
+) -> Result<()> {
+    let mut stream = state
+        .streams
+        .lock()
+        .unwrap()
+        .remove(&stream_id)
+        .ok_or_else(|| Error::StreamNotFound(stream_id.clone()))?;
+
+    // A stream stopped right after it started may not have named its task
+    // yet; wait for it, so the task does not keep running unseen.
+    let _ = tokio::time::timeout(STOP_TASK_WAIT, stream.task_id.wait_for(Option::is_some)).await;
+    stream.handle.abort();
+
+    let task_id = stream.task_id.borrow().clone();
+    if let Some(task_id) = task_id {
+        let tasks = get_tasks(&state)?;
+        tasks.cancel(&state.auth_context(), &task_id).await?;
+        cancel_task_requests(&app, &state.tool_requests, &task_id);
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn submit_tool_output(
     state: State<'_, RadkitRuntimeState>,
     payload: ToolOutputRequest,
-) -> Result<(), String> {
+) -> Result<()> {
     let mut requests = state.tool_requests.lock().unwrap();
-    if let Some(tx) = requests.remove(&payload.request_id) {
-        let result = if payload.is_error {
-             let msg = payload.result.as_str().unwrap_or("Unknown error");
-             ToolResult::error(msg)
-        } else {
-             ToolResult::success(payload.result)
-        };
-        let _ = tx.send(result);
+    if let Some(request) = requests.remove(&payload.request_id) {
+        request.resolve(payload);
         Ok(())
     } else {
-        Err(format!("Request ID {} not found", payload.request_id))
+        Err(Error::ToolRequestNotFound(payload.request_id))
+    }
+}
+
+#[tauri::command]
+pub async fn list_tool_requests(
+    state: State<'_, RadkitRuntimeState>,
+    request: ListToolRequestsRequest,
+) -> Result<Vec<ToolRequestInfo>> {
+    let requests = state.tool_requests.lock().unwrap();
+    let mut outstanding: Vec<ToolRequestInfo> = requests
+        .values()
+        .map(|r| &r.info)
+        .filter(|info| request.task_id.is_none() || info.task_id == request.task_id)
+        .filter(|info| request.context_id.is_none() || info.context_id == request.context_id)
+        .cloned()
+        .collect();
+    outstanding.sort_by_key(|info| info.sequence);
+    Ok(outstanding)
+}
+
+#[tauri::command]
+pub async fn store_credential(
+    state: State<'_, RadkitRuntimeState>,
+    request: StoreCredentialRequest,
+) -> Result<()> {
+    if request.name.trim().is_empty() {
+        return Err(Error::InvalidRequest("name must not be empty".into()));
     }
+    state.secrets.set(&request.name, &request.secret)
+}
+
+/// Reports whether a credential is stored, without revealing it.
+#[tauri::command]
+pub async fn has_credential(
+    state: State<'_, RadkitRuntimeState>,
+    request: CredentialRequest,
+) -> Result<bool> {
+    Ok(state.secrets.get(&request.name)?.is_some())
+}
+
+#[tauri::command]
+pub async fn delete_credential(
+    state: State<'_, RadkitRuntimeState>,
+    request: CredentialRequest,
+) -> Result<bool> {
+    state.secrets.delete(&request.name)
+}
+
+#[tauri::command]
+pub async fn set_auth_context(
+    state: State<'_, RadkitRuntimeState>,
+    request: SetAuthContextRequest,
+) -> Result<()> {
+    if request.user_name.trim().is_empty() {
+        return Err(Error::InvalidRequest("userName must not be empty".into()));
+    }
+    let app_name = request.app_name.unwrap_or_else(|| state.auth_context().app_name);
+    state.set_auth_context(AuthContext {
+        app_name,
+        user_name: request.user_name,
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_auth_context(
+    state: State<'_, RadkitRuntimeState>,
+) -> Result<AuthContextInfo> {
+    let auth = state.auth_context();
+    Ok(AuthContextInfo {
+        app_name: auth.app_name,
+        user_name: auth.user_name,
+    })
 }
 
 #[tauri::command]
 pub async fn search_memory(
     state: State<'_, RadkitRuntimeState>,
     request: SearchMemoryRequest,
-) -> Result<Vec<MemoryEntryResult>, String> {
+) -> Result<Vec<MemoryEntryResult>> {
     let runtime = get_runtime(&state)?;
     let memory = runtime.memory();
-    let auth = AuthContext {
-        app_name: "radkit-tauri".into(),
-        user_name: "user".into(),
-    };
+    let auth = state.auth_context();
 
     let options = SearchOptions {
         limit: Some(request.limit.unwrap_or(10)),
@@ -307,7 +630,10 @@ pub async fn search_memory(
         ..Default::default()
     };
 
-    let results = memory.search(&auth, &request.query, options).await.map_err(|e| e.to_string())?;
+    let results = memory
+        .search(&auth, &request.query, options)
+        .await
+        .map_err(|e| Error::MemoryBackend(e.to_string()))?;
 
     Ok(results.into_iter().map(|e| MemoryEntryResult {
         id: e.id,
@@ -321,13 +647,10 @@ pub async fn search_memory(
 pub async fn save_memory(
     state: State<'_, RadkitRuntimeState>,
     request: SaveMemoryRequest,
-) -> Result<String, String> {
+) -> Result<String> {
     let runtime = get_runtime(&state)?;
     let memory = runtime.memory();
-    let auth = AuthContext {
-        app_name: "radkit-tauri".into(),
-        user_name: "user".into(),
-    };
+    let auth = state.auth_context();
 
     let metadata: std::collections::HashMap<String, serde_json::Value> = request.metadata
         .and_then(|v| serde_json::from_value(v).ok())
@@ -339,7 +662,7 @@ pub async fn save_memory(
         metadata,
     };
 
-    let id = memory.add(&auth, content).await.map_err(|e| e.to_string())?;
+    let id = memory.add(&auth, content).await.map_err(|e| Error::MemoryBackend(e.to_string()))?;
     Ok(id)
 }
 
@@ -347,15 +670,15 @@ pub async fn save_memory(
 pub async fn delete_memory(
     state: State<'_, RadkitRuntimeState>,
     request: DeleteMemoryRequest,
-) -> Result<bool, String> {
+) -> Result<bool> {
     let runtime = get_runtime(&state)?;
     let memory = runtime.memory();
-    let auth = AuthContext {
-        app_name: "radkit-tauri".into(),
-        user_name: "user".into(),
-    };
+    let auth = state.auth_context();
 
-    let success = memory.delete(&auth, &request.id).await.map_err(|e| e.to_string())?;
+    let success = memory
+        .delete(&auth, &request.id)
+        .await
+        .map_err(|e| Error::MemoryBackend(e.to_string()))?;
     Ok(success)
 }
 
@@ -363,37 +686,74 @@ pub async fn delete_memory(
 pub async fn list_tasks(
     state: State<'_, RadkitRuntimeState>,
     request: ListTasksRequest,
-) -> Result<Vec<serde_json::Value>, String> {
-    let client = get_client(&state)?;
-    let tasks = client.list_tasks(request.context_id).await.map_err(|e| e.to_string())?;
-    Ok(serde_json::to_value(tasks).map_err(|e| e.to_string())?.as_array().unwrap_or(&vec![]).clone())
+) -> Result<Vec<serde_json::Value>> {
+    // The agent server has no tasks/list method, so read the task store
+    // directly, as the active user.
+    let tasks = get_tasks(&state)?;
+    let auth = state.auth_context();
+
+    let mut result = Vec::new();
+    for task in tasks.list_tasks(&auth).await? {
+        if request.context_id.as_ref().is_some_and(|id| *id != task.context_id) {
+            continue;
+        }
+        result.push(a2a_task(&tasks, &auth, task).await?);
+    }
+    Ok(result)
+}
+
+/// Rebuilds the A2A task for a stored task, with its message history.
+async fn a2a_task(tasks: &IdentifiedTaskStore, auth: &AuthContext, task: Task) -> Result<Value> {
+    let history: Vec<Message> = tasks
+        .get_events(auth, &task.id)
+        .await?
+        .into_iter()
+        .filter_map(|event| match event {
+            TaskEvent::Message(message) => Some(message),
+            TaskEvent::StatusUpdate(update) => update.status.message,
+            TaskEvent::ArtifactUpdate(_) => None,
+        })
+        .collect();
+    Ok(serde_json::to_value(a2a_types::Task {
+        kind: a2a_types::TASK_KIND.to_string(),
+        id: task.id,
+        context_id: task.context_id,
+        status: task.status,
+        history,
+        artifacts: task.artifacts,
+        metadata: None,
+    })?)
 }
 
 #[tauri::command]
 pub async fn get_task(
     state: State<'_, RadkitRuntimeState>,
     request: GetTaskRequest,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value> {
     let client = get_client(&state)?;
     let params = TaskQueryParams {
-         id: request.task_id,
+         id: request.task_id.clone(),
          history_length: None,
          metadata: None,
     };
-    let task = client.get_task(params).await.map_err(|e| e.to_string())?;
-    Ok(serde_json::to_value(task).map_err(|e| e.to_string())?)
+    let task = client.get_task(params).await.map_err(|e| Error::a2a(e, Some(&request.task_id)))?;
+    Ok(serde_json::to_value(task)?)
 }
 
 #[tauri::command]
-pub async fn cancel_task(
+pub async fn cancel_task<R: TauriRuntime>(
+    app: AppHandle<R>,
     state: State<'_, RadkitRuntimeState>,
     request: CancelTaskRequest,
-) -> Result<serde_json::Value, String> {
-    let client = get_client(&state)?;
-    let params = TaskIdParams {
-         id: request.task_id,
-         metadata: None,
-    };
-    let task = client.cancel_task(params).await.map_err(|e| e.to_string())?;
-    Ok(serde_json::to_value(task).map_err(|e| e.to_string())?)
+) -> Result<serde_json::Value> {
+    // radkit's server does not implement tasks/cancel, so cancel through
+    // the task store.
+    let tasks = get_tasks(&state)?;
+    let auth = state.auth_context();
+    let task = tasks
+        .cancel(&auth, &request.task_id)
+        .await?
+        .ok_or_else(|| Error::TaskNotFound(request.task_id.clone()))?;
+    cancel_task_requests(&app, &state.tool_requests, &request.task_id);
+    a2a_task(&tasks, &auth, task).await
 }
@@ -1,8 +1,6 @@
 use serde::de::DeserializeOwned;
 use tauri::{plugin::PluginApi, AppHandle, Runtime};
 
-use crate::models::*;
-
 pub fn init<R: Runtime, C: DeserializeOwned>(
   app: &AppHandle<R>,
   _api: PluginApi<R, C>,
@@ -14,9 +12,7 @@ pub fn init<R: Runtime, C: DeserializeOwned>(
 pub struct Radkit<R: Runtime>(AppHandle<R>);
 
 impl<R: Runtime> Radkit<R> {
-  pub fn ping(&self, payload: PingRequest) -> crate::Result<PingResponse> {
-    Ok(PingResponse {
-      value: payload.value,
-    })
+  pub(crate) fn app(&self) -> &AppHandle<R> {
+    &self.0
   }
 }
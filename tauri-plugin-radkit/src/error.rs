@@ -1,4 +1,8 @@
+use a2a_client::A2AError;
+use a2a_types::JSONRPCError;
+use radkit::errors::AgentError;
 use serde::{ser::Serializer, Serialize};
+use serde_json::{json, Value};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -9,6 +13,190 @@ pub enum Error {
   #[cfg(mobile)]
   #[error(transparent)]
   PluginInvoke(#[from] tauri::plugin::mobile::PluginInvokeError),
+  #[error("Agent is not initialized; call init_agent first")]
+  NotInitialized,
+  #[error("Invalid request: {0}")]
+  InvalidRequest(String),
+  #[error("Invalid provider configuration: {0}")]
+  ProviderConfig(String),
+  #[error("Provider request failed: {message}")]
+  ProviderHttp { status: Option<u16>, message: String },
+  #[error("Provider rate limit exceeded: {0}")]
+  RateLimited(String),
+  #[error("Tool {tool} timed out after {timeout_ms} ms")]
+  ToolTimeout { tool: String, timeout_ms: u64 },
+  #[error("Tool request {0} not found")]
+  ToolRequestNotFound(String),
+  #[error("Task {0} not found")]
+  TaskNotFound(String),
+  #[error("Stream {0} not found")]
+  StreamNotFound(String),
+  #[error("Memory backend error: {0}")]
+  MemoryBackend(String),
+  #[error("Secret store error: {0}")]
+  SecretStore(String),
+  #[error("A2A protocol error: {0}")]
+  A2aProtocol(String),
+  #[error("Agent server failed to start: {0}")]
+  ServerStart(String),
+  #[error("Agent error in {component}: {reason}")]
+  Agent { component: String, reason: String },
+  #[error(transparent)]
+  Serialization(#[from] serde_json::Error),
+  #[error(transparent)]
+  Tauri(#[from] tauri::Error),
+}
+
+impl Error {
+  /// Stable identifier the frontend can match on instead of the message.
+  pub fn code(&self) -> &'static str {
+    match self {
+      Error::Io(_) => "Io",
+      #[cfg(mobile)]
+      Error::PluginInvoke(_) => "PluginInvoke",
+      Error::NotInitialized => "NotInitialized",
+      Error::InvalidRequest(_) => "InvalidRequest",
+      Error::ProviderConfig(_) => "ProviderConfig",
+      Error::ProviderHttp { .. } => "ProviderHttp",
+      Error::RateLimited(_) => "RateLimited",
+      Error::ToolTimeout { .. } => "ToolTimeout",
+      Error::ToolRequestNotFound(_) => "ToolRequestNotFound",
+      Error::TaskNotFound(_) => "TaskNotFound",
+      Error::StreamNotFound(_) => "StreamNotFound",
+      Error::MemoryBackend(_) => "MemoryBackend",
+      Error::SecretStore(_) => "SecretStore",
+      Error::A2aProtocol(_) => "A2aProtocol",
+      Error::ServerStart(_) => "ServerStart",
+      Error::Agent { .. } => "Agent",
+      Error::Serialization(_) => "Serialization",
+      Error::Tauri(_) => "Tauri",
+    }
+  }
+
+  /// Structured fields for the variants that carry more than a message.
+  pub fn details(&self) -> Option<Value> {
+    match self {
+      Error::ProviderHttp { status, .. } => Some(json!({ "status": status })),
+      Error::ToolTimeout { tool, timeout_ms } => Some(json!({ "tool": tool, "timeoutMs": timeout_ms })),
+      Error::ToolRequestNotFound(id) => Some(json!({ "requestId": id })),
+      Error::TaskNotFound(id) => Some(json!({ "taskId": id })),
+      Error::StreamNotFound(id) => Some(json!({ "streamId": id })),
+      Error::Agent { component, .. } => Some(json!({ "component": component })),
+      _ => None,
+    }
+  }
+
+  /// Classifies an error returned by the A2A client.
+  pub fn a2a(error: A2AError, task_id: Option<&str>) -> Self {
+    match (&error, task_id) {
+      (A2AError::RemoteAgentError { code: Some(TASK_NOT_FOUND_CODE), .. }, Some(id)) => Error::TaskNotFound(id.to_string()),
+      (A2AError::RemoteAgentError { code: Some(INVALID_PARAMS_CODE), message }, _) => Error::InvalidRequest(message.clone()),
+      _ => Error::A2aProtocol(error.to_string()),
+    }
+  }
+
+  /// Classifies a JSON-RPC error response from the agent server.
+  pub fn jsonrpc(error: &JSONRPCError, task_id: Option<&str>) -> Self {
+    match task_id {
+      Some(id) if error.code == TASK_NOT_FOUND_CODE => Error::TaskNotFound(id.to_string()),
+      _ if error.code == INVALID_PARAMS_CODE => Error::InvalidRequest(error.message.clone()),
+      _ => Error::A2aProtocol(error.message.clone()),
+    }
+  }
+
+  /// The variant's own message, without the prefix its `Display` adds.
+  fn reason(&self) -> String {
+    match self {
+      Error::InvalidRequest(reason)
+      | Error::ProviderConfig(reason)
+      | Error::RateLimited(reason)
+      | Error::MemoryBackend(reason)
+      | Error::SecretStore(reason)
+      | Error::A2aProtocol(reason)
+      | Error::ServerStart(reason)
+      | Error::Agent { reason, .. }
+      | Error::ProviderHttp { message: reason, .. } => reason.clone(),
+      other => other.to_string(),
+    }
+  }
+
+  /// Encodes the error for the message of a failed task, so the command
+  /// that ran the task can rebuild it with [`Error::from_failure`].
+  pub fn to_failure(&self) -> Value {
+    json!({ "code": self.code(), "reason": self.reason(), "details": self.details() })
+  }
+
+  /// Rebuilds an error encoded by [`Error::to_failure`].
+  pub fn from_failure(failure: &Value) -> Self {
+    let reason = failure["reason"].as_str().unwrap_or_default().to_string();
+    let details = &failure["details"];
+    let detail = |key: &str| details[key].as_str().unwrap_or_default().to_string();
+    match failure["code"].as_str().unwrap_or_default() {
+      "InvalidRequest" => Error::InvalidRequest(reason),
+      "ProviderConfig" => Error::ProviderConfig(reason),
+      "ProviderHttp" => Error::ProviderHttp {
+        status: details["status"].as_u64().and_then(|s| u16::try_from(s).ok()),
+        message: reason,
+      },
+      "RateLimited" => Error::RateLimited(reason),
+      "ToolTimeout" => Error::ToolTimeout {
+        tool: detail("tool"),
+        timeout_ms: details["timeoutMs"].as_u64().unwrap_or_default(),
+      },
+      "TaskNotFound" => Error::TaskNotFound(detail("taskId")),
+      "MemoryBackend" => Error::MemoryBackend(reason),
+      "SecretStore" => Error::SecretStore(reason),
+      "ServerStart" => Error::ServerStart(reason),
+      _ => Error::Agent {
+        component: details["component"].as_str().unwrap_or("agent").to_string(),
+        reason,
+      },
+    }
+  }
+}
+
+/// JSON-RPC error code of the A2A `TaskNotFoundError`.
+const TASK_NOT_FOUND_CODE: i32 = -32001;
+
+/// JSON-RPC error code the agent server answers rejected input with, such as
+/// a message for a context that already has a task running.
+const INVALID_PARAMS_CODE: i32 = -32602;
+
+/// The status code radkit's providers put at the start of an HTTP error,
+/// as in `HTTP 500 Internal Server Error: ...`.
+fn http_status(message: &str) -> Option<u16> {
+  message.strip_prefix("HTTP ")?.get(..3)?.parse().ok()
+}
+
+impl From<AgentError> for Error {
+  fn from(error: AgentError) -> Self {
+    match error {
+      AgentError::LlmRateLimit { provider } => Error::RateLimited(provider),
+      AgentError::LlmAuthentication { provider } => {
+        Error::ProviderConfig(format!("{} rejected the API key", provider))
+      }
+      AgentError::LlmProvider { provider, message } => Error::ProviderHttp {
+        status: http_status(&message),
+        message: format!("{}: {}", provider, message),
+      },
+      AgentError::Network { operation, reason } => Error::ProviderHttp {
+        status: None,
+        message: format!("{}: {}", operation, reason),
+      },
+      error @ (AgentError::InvalidConfiguration { .. } | AgentError::MissingConfiguration { .. }) => {
+        Error::ProviderConfig(error.to_string())
+      }
+      AgentError::ToolTimeout { tool_name, timeout_ms } => Error::ToolTimeout { tool: tool_name, timeout_ms },
+      AgentError::TaskNotFound { task_id } => Error::TaskNotFound(task_id),
+      AgentError::InvalidInput(reason) => Error::InvalidRequest(reason),
+      AgentError::Memory(reason) => Error::MemoryBackend(reason),
+      AgentError::Internal { component, reason } => Error::Agent { component, reason },
+      other => Error::Agent {
+        component: "radkit".into(),
+        reason: other.to_string(),
+      },
+    }
+  }
 }
 
 impl Serialize for Error {
@@ -16,6 +204,19 @@ impl Serialize for Error {
   where
     S: Serializer,
   {
-    serializer.serialize_str(self.to_string().as_ref())
+    #[derive(Serialize)]
+    struct Payload<'a> {
+      code: &'a str,
+      message: String,
+      #[serde(skip_serializing_if = "Option::is_none")]
+      details: Option<Value>,
+    }
+
+    Payload {
+      code: self.code(),
+      message: self.to_string(),
+      details: self.details(),
+    }
+    .serialize(serializer)
   }
 }
@@ -1,17 +1,88 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use serde_json::Value;
 use radkit::tools::{BaseTool, FunctionDeclaration, ToolResult, ToolContext};
 use tauri::{AppHandle, Emitter, Runtime};
 use tokio::sync::oneshot;
 use uuid::Uuid;
+use crate::chat_skill::{CONTEXT_ID_KEY, INPUT_REQUIRED_KEY, TASK_ID_KEY, TOOL_CALL_ID_KEY};
+use crate::models::{ToolOutputRequest, ToolRequestInfo};
+use crate::Error;
+
+/// Orders tool requests across all tasks, so the frontend can tell which
+/// came first.
+static NEXT_SEQUENCE: AtomicU64 = AtomicU64::new(1);
+
+/// A tool call waiting for the frontend to submit its output.
+pub struct PendingToolRequest {
+    pub info: ToolRequestInfo,
+    sender: oneshot::Sender<ToolOutputRequest>,
+}
+
+impl PendingToolRequest {
+    pub fn resolve(self, output: ToolOutputRequest) {
+        let _ = self.sender.send(output);
+    }
+}
+
+pub type PendingToolRequests = Arc<Mutex<HashMap<String, PendingToolRequest>>>;
+
+/// Drops every pending request belonging to `task_id`, failing the waiting
+/// tool calls and telling the frontend to dismiss them.
+pub fn cancel_task_requests<R: Runtime>(app: &AppHandle<R>, pending: &PendingToolRequests, task_id: &str) {
+    let cancelled: Vec<(String, PendingToolRequest)> = {
+        let mut pending = pending.lock().unwrap();
+        let ids: Vec<String> = pending
+            .iter()
+            .filter(|(_, request)| request.info.task_id.as_deref() == Some(task_id))
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.into_iter().filter_map(|id| pending.remove(&id).map(|r| (id, r))).collect()
+    };
+
+    for (request_id, request) in cancelled {
+        emit_cancelled(app, &request_id, &request, "cancelled");
+    }
+}
+
+fn emit_cancelled<R: Runtime>(app: &AppHandle<R>, request_id: &str, request: &PendingToolRequest, reason: &str) {
+    let _ = app.emit("tool_execution_cancelled", serde_json::json!({
+        "requestId": request_id,
+        "name": request.info.name,
+        "taskId": request.info.task_id,
+        "contextId": request.info.context_id,
+        "toolCallId": request.info.tool_call_id,
+        "reason": reason,
+    }));
+}
+
+/// Removes the pending entry however `run_async` exits, so timeouts, emit
+/// failures and aborted tasks do not leak entries.
+struct PendingGuard<'a, R: Runtime> {
+    app: &'a AppHandle<R>,
+    pending: &'a PendingToolRequests,
+    request_id: String,
+    reason: &'static str,
+}
+
+impl<R: Runtime> Drop for PendingGuard<'_, R> {
+    fn drop(&mut self) {
+        let removed = self.pending.lock().unwrap().remove(&self.request_id);
+        if let Some(request) = removed {
+            emit_cancelled(self.app, &self.request_id, &request, self.reason);
+        }
+    }
+}
 
 pub struct FrontendTool<R: Runtime> {
     name: String,
     description: String,
     parameters: Value,
+    timeout: Option<Duration>,
     app_handle: AppHandle<R>,
-    pending_requests: Arc<Mutex<HashMap<String, oneshot::Sender<ToolResult>>>>,
+    pending_requests: PendingToolRequests,
 }
 
 impl<R: Runtime> FrontendTool<R> {
@@ -19,13 +90,15 @@ impl<R: Runtime> FrontendTool<R> {
         name: String,
         description: String,
         parameters: Value,
+        timeout: Option<Duration>,
         app_handle: AppHandle<R>,
-        pending_requests: Arc<Mutex<HashMap<String, oneshot::Sender<ToolResult>>>>,
+        pending_requests: PendingToolRequests,
     ) -> Self {
         Self {
             name,
             description,
             parameters,
+            timeout,
             app_handle,
             pending_requests,
         }
@@ -53,29 +126,66 @@ impl<R: Runtime> BaseTool for FrontendTool<R> {
     async fn run_async(
         &self,
         args: HashMap<String, Value>,
-        _context: &ToolContext<'_>,
+        context: &ToolContext<'_>,
     ) -> ToolResult {
         let request_id = Uuid::new_v4().to_string();
+        let state_string = |key: &str| context.state().get_state(key).and_then(|v| v.as_str().map(str::to_string));
+        let info = ToolRequestInfo {
+            request_id: request_id.clone(),
+            name: self.name.clone(),
+            args: args.into_iter().collect(),
+            task_id: state_string(TASK_ID_KEY),
+            context_id: state_string(CONTEXT_ID_KEY),
+            tool_call_id: state_string(TOOL_CALL_ID_KEY),
+            sequence: NEXT_SEQUENCE.fetch_add(1, Ordering::SeqCst),
+        };
         let (tx, rx) = oneshot::channel();
 
         {
             let mut pending = self.pending_requests.lock().unwrap();
-            pending.insert(request_id.clone(), tx);
+            pending.insert(request_id.clone(), PendingToolRequest {
+                info: info.clone(),
+                sender: tx,
+            });
         }
+        let mut guard = PendingGuard {
+            app: &self.app_handle,
+            pending: &self.pending_requests,
+            request_id: request_id.clone(),
+            reason: "aborted",
+        };
 
-        let event_payload = serde_json::json!({
-            "requestId": request_id,
-            "name": self.name,
-            "args": args,
-        });
-
-        if let Err(e) = self.app_handle.emit("tool_execution_request", event_payload) {
+        if let Err(e) = self.app_handle.emit("tool_execution_request", info) {
             return ToolResult::error(format!("Failed to emit event: {}", e));
         }
 
-        match rx.await {
-            Ok(result) => result,
-            Err(_) => ToolResult::error("Tool execution cancelled or channel closed"),
+        let received = match self.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, rx).await {
+                Ok(received) => received,
+                Err(_) => {
+                    guard.reason = "timeout";
+                    let error = Error::ToolTimeout {
+                        tool: self.name.clone(),
+                        timeout_ms: timeout.as_millis() as u64,
+                    };
+                    return ToolResult::error(error.to_string());
+                }
+            },
+            None => rx.await,
+        };
+        let output = match received {
+            Ok(output) => output,
+            Err(_) => return ToolResult::error("Tool execution cancelled or channel closed"),
+        };
+
+        if let Some(question) = output.input_required {
+            context.state().set_state(INPUT_REQUIRED_KEY, Value::String(question));
+        }
+        if output.is_error {
+            let msg = output.result.as_str().unwrap_or("Unknown error");
+            ToolResult::error(msg)
+        } else {
+            ToolResult::success(output.result)
         }
     }
 }
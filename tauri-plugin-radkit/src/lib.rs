@@ -1,38 +1,174 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use radkit::agent::{RegisteredSkill, SkillHandler};
+use radkit::tools::BaseTool;
 use tauri::{
-  plugin::{Builder, TauriPlugin},
+  plugin::{Builder as PluginBuilder, TauriPlugin},
   Manager, Runtime,
 };
 
 pub use models::*;
-use runtime_holder::RadkitRuntimeState;
+use runtime_holder::{PluginConfig, RadkitRuntimeState};
+use secret_store::SecretStore;
 
+#[cfg(desktop)]
+mod desktop;
+#[cfg(mobile)]
+mod mobile;
+
+mod api;
 mod commands;
 mod error;
 mod models;
+mod llm_factory;
 mod runtime_holder;
 mod frontend_tool;
 mod chat_skill;
+mod scripted_llm;
+mod chat_completions;
+mod cassette;
+mod task_store;
+mod memory_store;
+mod streams;
+mod backend_tool;
+mod secret_store;
 
 pub use error::{Error, Result};
 
-/// Initializes the plugin.
+#[cfg(desktop)]
+pub use desktop::Radkit;
+#[cfg(mobile)]
+pub use mobile::Radkit;
+pub use streams::{StreamMessage, STREAM_BUFFER};
+pub use secret_store::SecretStoreConfig;
+
+/// Extensions to [`tauri::App`], [`tauri::AppHandle`] and [`tauri::Window`] to access the radkit APIs.
+pub trait RadkitExt<R: Runtime> {
+  fn radkit(&self) -> &Radkit<R>;
+}
+
+impl<R: Runtime, T: Manager<R>> crate::RadkitExt<R> for T {
+  fn radkit(&self) -> &Radkit<R> {
+    self.state::<Radkit<R>>().inner()
+  }
+}
+
+/// Initializes the plugin with the default configuration.
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
-  Builder::new("radkit")
-    .invoke_handler(tauri::generate_handler![
-        commands::init_agent,
-        commands::chat,
-        commands::stream_chat,
-        commands::submit_tool_output,
-        commands::search_memory,
-        commands::save_memory,
-        commands::delete_memory,
-        commands::list_tasks,
-        commands::get_task,
-        commands::cancel_task
-    ])
-    .setup(|app, _api| {
-      app.manage(RadkitRuntimeState::new());
-      Ok(())
-    })
-    .build()
+  Builder::new().build()
+}
+
+/// Configures the plugin from the host app's Rust code.
+#[derive(Default)]
+pub struct Builder {
+  config: PluginConfig,
+  agent: Option<InitAgentRequest>,
+}
+
+impl Builder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Registers a native tool. It is offered to the model alongside the
+  /// frontend tools of every agent `init_agent` creates, and runs in Rust
+  /// without a round-trip through the webview.
+  pub fn with_tool(mut self, tool: impl BaseTool + 'static) -> Self {
+    self.config.tools.push(Arc::new(tool));
+    self
+  }
+
+  /// Adds a skill next to the built-in chat skill. `factory` is called
+  /// each time an agent is built.
+  pub fn with_skill<S, F>(mut self, factory: F) -> Self
+  where
+    S: SkillHandler + RegisteredSkill + 'static,
+    F: Fn() -> S + Send + Sync + 'static,
+  {
+    self.config.skills.push(Box::new(move |agent| agent.with_skill(factory())));
+    self
+  }
+
+  /// Starts the agent during plugin setup, so the frontend can chat
+  /// without calling `init_agent` or ever seeing the API keys.
+  pub fn with_agent(mut self, config: InitAgentRequest) -> Self {
+    self.agent = Some(config);
+    self
+  }
+
+  /// Address the embedded A2A server listens on. Defaults to
+  /// `127.0.0.1:0`, a free local port.
+  pub fn bind_address(mut self, address: impl Into<String>) -> Self {
+    self.config.bind_address = address.into();
+    self
+  }
+
+  /// Directory the file-backed task and memory stores use when their
+  /// config has no `path`. Defaults to `radkit` under the app data directory.
+  pub fn data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.config.data_dir = Some(dir.into());
+    self
+  }
+
+  /// Where `store_credential` keeps provider keys. Defaults to the OS
+  /// keyring, or an encrypted file when `RADKIT_SECRETS_PASSPHRASE` is set.
+  pub fn secret_store(mut self, store: SecretStoreConfig) -> Self {
+    self.config.secret_store = Some(store);
+    self
+  }
+
+  /// Whether the frontend may (re)configure the agent with `init_agent`.
+  /// Turn this off when the agent is set up with `with_agent`.
+  pub fn allow_frontend_init(mut self, allow: bool) -> Self {
+    self.config.allow_frontend_init = allow;
+    self
+  }
+
+  pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
+    let Builder { config, agent } = self;
+    PluginBuilder::new("radkit")
+      .invoke_handler(tauri::generate_handler![
+          commands::init_agent,
+          commands::validate_llm_config,
+          commands::update_llm,
+          commands::chat,
+          commands::stream_chat,
+          commands::stop_stream,
+          commands::submit_tool_output,
+          commands::list_tool_requests,
+          commands::store_credential,
+          commands::has_credential,
+          commands::delete_credential,
+          commands::set_auth_context,
+          commands::get_auth_context,
+          commands::search_memory,
+          commands::save_memory,
+          commands::delete_memory,
+          commands::list_tasks,
+          commands::get_task,
+          commands::cancel_task
+      ])
+      .setup(move |app, api| {
+        #[cfg(mobile)]
+        let radkit = mobile::init(app, api)?;
+        #[cfg(desktop)]
+        let radkit = desktop::init(app, api)?;
+        app.manage(radkit);
+        let secrets = SecretStore::open(app, &config)?;
+        app.manage(RadkitRuntimeState::new(config, secrets));
+
+        if let Some(agent) = agent {
+          let app = app.clone();
+          tauri::async_runtime::spawn(async move {
+            let state = app.state::<RadkitRuntimeState>();
+            if let Err(e) = commands::start_agent(&app, &state, agent).await {
+              eprintln!("Radkit agent failed to start: {}", e);
+              *state.start_failure.lock().unwrap() = Some(e.to_failure());
+            }
+          });
+        }
+        Ok(())
+      })
+      .build()
+  }
 }
@@ -0,0 +1,869 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use arc_swap::ArcSwap;
+use radkit::errors::AgentResult;
+use radkit::models::{BaseLlm, LlmResponse, Thread};
+use radkit::models::providers::{
+    OpenAILlm, AnthropicLlm, GeminiLlm, OpenRouterLlm, GrokLlm, DeepSeekLlm
+};
+use radkit::tools::BaseToolset;
+use serde_json::json;
+use tokio::sync::mpsc::UnboundedSender;
+use crate::cassette::Cassette;
+use crate::chat_completions::ChatCompletionsLlm;
+use crate::models::{ChatOptions, CommonLlmConfig, LlmConfig};
+use crate::scripted_llm::ScriptedLlm;
+use crate::secret_store::SecretStore;
+use crate::{Error, Result};
+
+/// Replaces a `credential` reference with the stored key it names, unless
+/// an explicit `api_key` is set. Without either, the provider later reads
+/// its environment variable.
+fn resolve_credential(config: &mut LlmConfig, secrets: &SecretStore) -> Result<()> {
+    let (api_key, credential) = match config {
+        LlmConfig::OpenAI { api_key, credential, .. }
+        | LlmConfig::Anthropic { api_key, credential, .. }
+        | LlmConfig::Gemini { api_key, credential, .. }
+        | LlmConfig::OpenRouter { api_key, credential, .. }
+        | LlmConfig::Grok { api_key, credential, .. }
+        | LlmConfig::DeepSeek { api_key, credential, .. }
+        | LlmConfig::OpenAICompatible { api_key, credential, .. } => (api_key, credential),
+        LlmConfig::Scripted { .. } => return Ok(()),
+    };
+    if api_key.is_some() {
+        return Ok(());
+    }
+    if let Some(name) = credential.take() {
+        match secrets.get(&name)? {
+            Some(key) => *api_key = Some(key),
+            None => return Err(Error::ProviderConfig(format!("No stored credential named {}", name))),
+        }
+    }
+    Ok(())
+}
+
+/// Applies the per-request model, temperature and token limit in `options`.
+fn apply_overrides(config: &mut LlmConfig, options: &ChatOptions) -> Result<()> {
+    match config {
+        LlmConfig::OpenAI { model, common, .. }
+        | LlmConfig::Anthropic { model, common, .. }
+        | LlmConfig::Gemini { model, common, .. }
+        | LlmConfig::OpenRouter { model, common, .. }
+        | LlmConfig::Grok { model, common, .. }
+        | LlmConfig::DeepSeek { model, common, .. }
+        | LlmConfig::OpenAICompatible { model, common, .. } => {
+            if let Some(name) = &options.model {
+                *model = name.clone();
+            }
+            if options.temperature.is_some() || options.max_tokens.is_some() {
+                let common = common.get_or_insert_with(CommonLlmConfig::default);
+                common.temperature = options.temperature.or(common.temperature);
+                common.max_tokens = options.max_tokens.or(common.max_tokens);
+            }
+        }
+        // The model is only a label here; `DynamicLlm::for_request` renames
+        // the running script rather than rebuilding it.
+        LlmConfig::Scripted { .. } => {
+            if options.temperature.is_some() || options.max_tokens.is_some() {
+                return Err(Error::ProviderConfig("Scripted does not support temperature, maxTokens".into()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rejects options in `common` that `provider` does not accept, rather than
+/// dropping them silently. `supported` holds snake_case field names.
+fn check_options(provider: &str, common: &CommonLlmConfig, supported: &[&str]) -> Result<()> {
+    let set = [
+        ("temperature", "temperature", common.temperature.is_some()),
+        ("max_tokens", "maxTokens", common.max_tokens.is_some()),
+        ("top_p", "topP", common.top_p.is_some()),
+        ("stop", "stop", common.stop.is_some()),
+        ("seed", "seed", common.seed.is_some()),
+        ("reasoning_effort", "reasoningEffort", common.reasoning_effort.is_some()),
+        ("response_format", "responseFormat", common.response_format.is_some()),
+        ("headers", "headers", common.headers.is_some()),
+        ("extra", "extra", common.extra.is_some()),
+    ];
+    let unsupported: Vec<&str> = set
+        .iter()
+        .filter(|(field, _, is_set)| *is_set && !supported.contains(field))
+        .map(|(_, name, _)| *name)
+        .collect();
+    if unsupported.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::ProviderConfig(format!("{} does not support {}", provider, unsupported.join(", "))))
+    }
+}
+
+/// The `CommonLlmConfig` options radkit's Anthropic and Gemini providers
+/// can apply.
+const RADKIT_OPTIONS: &[&str] = &["temperature", "max_tokens"];
+
+/// Every `CommonLlmConfig` option, all of which the chat completions client
+/// sends.
+const CHAT_COMPLETIONS_OPTIONS: &[&str] = &[
+    "temperature",
+    "max_tokens",
+    "top_p",
+    "stop",
+    "seed",
+    "reasoning_effort",
+    "response_format",
+    "headers",
+    "extra",
+];
+
+/// DeepSeek's API has no seed or reasoning effort setting.
+const DEEPSEEK_OPTIONS: &[&str] = &[
+    "temperature",
+    "max_tokens",
+    "top_p",
+    "stop",
+    "response_format",
+    "headers",
+    "extra",
+];
+
+const OPENAI_URL: &str = "https://api.openai.com/v1/chat/completions";
+const GROK_URL: &str = "https://api.x.ai/v1/chat/completions";
+const DEEPSEEK_URL: &str = "https://api.deepseek.com/v1/chat/completions";
+const OPENROUTER_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+
+/// Applies the supported `CommonLlmConfig` options through the provider's
+/// builder methods, after checking that no other option is set.
+macro_rules! apply_common {
+    ($llm:ident, $common:expr, $provider:literal) => {
+        if let Some(c) = $common {
+            check_options($provider, &c, RADKIT_OPTIONS)?;
+            if let Some(temp) = c.temperature { $llm = $llm.with_temperature(temp); }
+            if let Some(mt) = c.max_tokens { $llm = $llm.with_max_tokens(mt); }
+        }
+    };
+}
+
+/// Builds a radkit provider from its key, or its environment variable when
+/// none is set, at `endpoint` if one is given, then applies the common
+/// options.
+macro_rules! hosted {
+    ($ty:ident, $provider:literal, $model:expr, $api_key:expr, $common:expr, $endpoint:expr) => {{
+        let mut llm = match $api_key {
+            Some(key) => $ty::new($model, key),
+            None => $ty::from_env($model).map_err(|e| Error::ProviderConfig(e.to_string()))?,
+        };
+        if let Some(url) = $endpoint {
+            llm = llm.with_base_url(url);
+        }
+        apply_common!(llm, $common, $provider);
+        llm
+    }};
+}
+
+/// The key given, else the one in the provider's environment variable.
+fn hosted_key(api_key: Option<String>, variable: &str) -> Result<String> {
+    api_key
+        .or_else(|| std::env::var(variable).ok())
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| Error::ProviderConfig(format!("Missing configuration: {}", variable)))
+}
+
+/// Builds a chat completions provider, after checking `common` against the
+/// options it accepts.
+fn chat_completions(
+    provider: &'static str,
+    model: String,
+    url: String,
+    api_key: Option<String>,
+    common: Option<CommonLlmConfig>,
+    supported: &[&str],
+) -> Result<ChatCompletionsLlm> {
+    let common = common.unwrap_or_default();
+    check_options(provider, &common, supported)?;
+    Ok(ChatCompletionsLlm::new(provider, model, url, api_key).with_common(common))
+}
+
+/// Builds the LLM described by `config`. Nothing is sent to the provider,
+/// so a successful build only means the configuration is complete: the key
+/// is found and every option is supported.
+pub fn build_llm(mut config: LlmConfig, secrets: &SecretStore) -> Result<Arc<dyn BaseLlm>> {
+    resolve_credential(&mut config, secrets)?;
+    Ok(build_resolved(config)?.base())
+}
+
+/// Builds from a config whose credential has already been resolved.
+fn build_resolved(config: LlmConfig) -> Result<ProviderLlm> {
+    build_at(config, None)
+}
+
+/// Builds from a resolved config. `endpoint` replaces a hosted provider's
+/// full API URL, so tests can point it at a local server.
+fn build_at(config: LlmConfig, endpoint: Option<String>) -> Result<ProviderLlm> {
+    let url = |default: &str| endpoint.clone().unwrap_or_else(|| default.to_string());
+    let llm = match config {
+        LlmConfig::OpenAI { model, api_key, common, .. } => ProviderLlm::ChatCompletions(Arc::new(chat_completions(
+            "OpenAI",
+            model,
+            url(OPENAI_URL),
+            Some(hosted_key(api_key, OpenAILlm::API_KEY_ENV)?),
+            common,
+            CHAT_COMPLETIONS_OPTIONS,
+        )?)),
+        LlmConfig::Anthropic { model, api_key, common, .. } => ProviderLlm::Radkit(Arc::new(hosted!(
+            AnthropicLlm, "Anthropic", model, api_key, common, endpoint
+        ))),
+        LlmConfig::Gemini { model, api_key, common, .. } => ProviderLlm::Radkit(Arc::new(hosted!(
+            GeminiLlm, "Gemini", model, api_key, common, endpoint
+        ))),
+        LlmConfig::OpenRouter { model, api_key, site_url, app_name, common, .. } => {
+            let mut common = common.unwrap_or_default();
+            check_options("OpenRouter", &common, CHAT_COMPLETIONS_OPTIONS)?;
+            let api_key = hosted_key(api_key, OpenRouterLlm::API_KEY_ENV)?;
+            let mut llm = ChatCompletionsLlm::new("OpenRouter", model, url(OPENROUTER_URL), Some(api_key));
+            if let Some(url) = site_url { llm = llm.with_header("HTTP-Referer", url); }
+            if let Some(name) = app_name { llm = llm.with_header("X-Title", name); }
+            // OpenRouter takes the effort inside its own `reasoning` object.
+            if let Some(effort) = common.reasoning_effort.take() {
+                llm = llm.with_option("reasoning", json!({ "effort": effort }));
+            }
+            ProviderLlm::ChatCompletions(Arc::new(llm.with_common(common)))
+        }
+        LlmConfig::Grok { model, api_key, common, .. } => ProviderLlm::ChatCompletions(Arc::new(chat_completions(
+            "Grok",
+            model,
+            url(GROK_URL),
+            Some(hosted_key(api_key, GrokLlm::API_KEY_ENV)?),
+            common,
+            CHAT_COMPLETIONS_OPTIONS,
+        )?)),
+        LlmConfig::DeepSeek { model, api_key, common, .. } => ProviderLlm::ChatCompletions(Arc::new(chat_completions(
+            "DeepSeek",
+            model,
+            url(DEEPSEEK_URL),
+            Some(hosted_key(api_key, DeepSeekLlm::API_KEY_ENV)?),
+            common,
+            DEEPSEEK_OPTIONS,
+        )?)),
+        LlmConfig::OpenAICompatible { base_url, model, api_key, common, .. } => {
+            // Local servers usually take no key, so there is no environment
+            // fallback here.
+            let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+            ProviderLlm::ChatCompletions(Arc::new(chat_completions(
+                "OpenAICompatible",
+                model,
+                url,
+                api_key,
+                common,
+                CHAT_COMPLETIONS_OPTIONS,
+            )?))
+        }
+        LlmConfig::Scripted { model, turns, script_path } => {
+            let model = model.unwrap_or_else(|| "scripted".to_string());
+            let llm = match (turns, script_path) {
+                (Some(turns), _) => ScriptedLlm::new(model, turns),
+                (None, Some(path)) => ScriptedLlm::from_file(model, path)?,
+                (None, None) => return Err(Error::ProviderConfig("Scripted provider requires `turns` or `scriptPath`".into())),
+            };
+            ProviderLlm::Scripted(Arc::new(llm))
+        }
+    };
+    Ok(llm)
+}
+
+/// A built provider. The chat completions providers use the plugin's own
+/// client so replies can be streamed; scripts are kept apart so a request
+/// can rename one without starting it over.
+#[derive(Clone)]
+enum ProviderLlm {
+    ChatCompletions(Arc<ChatCompletionsLlm>),
+    Scripted(Arc<ScriptedLlm>),
+    Radkit(Arc<dyn BaseLlm>),
+}
+
+impl ProviderLlm {
+    fn base(&self) -> Arc<dyn BaseLlm> {
+        match self {
+            ProviderLlm::ChatCompletions(llm) => llm.clone(),
+            ProviderLlm::Scripted(llm) => llm.clone(),
+            ProviderLlm::Radkit(llm) => llm.clone(),
+        }
+    }
+}
+
+/// A built provider and the resolved config it was built from, kept so
+/// per-request overrides can rebuild it with a different model.
+struct Provider {
+    config: LlmConfig,
+    llm: ProviderLlm,
+}
+
+/// The provider the running agent talks to. `update_llm` replaces it in
+/// place; requests already in flight keep the provider they started with.
+pub struct LlmSlot {
+    provider: ArcSwap<Provider>,
+}
+
+impl LlmSlot {
+    pub fn new(config: LlmConfig, secrets: &SecretStore) -> Result<Self> {
+        let provider = Provider::build(config, secrets)?;
+        Ok(Self {
+            provider: ArcSwap::from_pointee(provider),
+        })
+    }
+
+    pub fn swap(&self, config: LlmConfig, secrets: &SecretStore) -> Result<()> {
+        let provider = Provider::build(config, secrets)?;
+        self.provider.store(Arc::new(provider));
+        Ok(())
+    }
+}
+
+impl Provider {
+    fn build(mut config: LlmConfig, secrets: &SecretStore) -> Result<Self> {
+        resolve_credential(&mut config, secrets)?;
+        let llm = build_resolved(config.clone())?;
+        Ok(Self { config, llm })
+    }
+}
+
+/// The LLM handed to the runtime: whatever `slot` currently holds, recorded
+/// or replayed through `cassette` when one is configured.
+#[derive(Clone)]
+pub struct DynamicLlm {
+    pub slot: Arc<LlmSlot>,
+    pub cassette: Option<Arc<Cassette>>,
+}
+
+impl DynamicLlm {
+    /// The LLM to use for one request: the current provider, rebuilt with
+    /// the model, temperature and token limit in `options` if any are set.
+    /// The result does not follow later swaps, so a request's tool rounds
+    /// all use the same LLM.
+    pub fn for_request(&self, options: &ChatOptions) -> Result<RequestLlm> {
+        let provider = self.slot.provider.load_full();
+        let inner = if options.overrides_llm() {
+            let mut config = provider.config.clone();
+            apply_overrides(&mut config, options)?;
+            match &provider.llm {
+                // Rebuilding would start the script over from its first turn.
+                ProviderLlm::Scripted(script) => {
+                    let model = options.model.clone().unwrap_or_else(|| script.model_name().to_string());
+                    ProviderLlm::Scripted(Arc::new(script.renamed(model)))
+                }
+                _ => build_resolved(config)?,
+            }
+        } else {
+            provider.llm.clone()
+        };
+        Ok(RequestLlm {
+            inner,
+            cassette: self.cassette.clone(),
+        })
+    }
+
+    fn current(&self) -> RequestLlm {
+        RequestLlm {
+            inner: self.slot.provider.load().llm.clone(),
+            cassette: self.cassette.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BaseLlm for DynamicLlm {
+    /// The provider can change between requests, so no single model name
+    /// applies here; the LLM `for_request` returns reports the one a request
+    /// uses.
+    fn model_name(&self) -> &str {
+        "dynamic"
+    }
+    async fn generate_content(
+        &self,
+        thread: Thread,
+        toolset: Option<Arc<dyn BaseToolset>>,
+    ) -> AgentResult<LlmResponse> {
+        self.current().generate_content(thread, toolset).await
+    }
+}
+
+/// The LLM one request uses, recorded or replayed through `cassette` when
+/// one is configured.
+pub struct RequestLlm {
+    inner: ProviderLlm,
+    cassette: Option<Arc<Cassette>>,
+}
+
+impl RequestLlm {
+    /// Generates the next reply and sends its text to `deltas`: piece by
+    /// piece while a chat completions provider streams it, otherwise whole
+    /// once it is complete, as when replaying a cassette.
+    pub async fn generate(
+        &self,
+        thread: Thread,
+        toolset: Option<Arc<dyn BaseToolset>>,
+        deltas: &UnboundedSender<String>,
+    ) -> AgentResult<LlmResponse> {
+        let streamed = AtomicBool::new(false);
+        let streamed_flag = &streamed;
+        let call = move |thread: Thread, toolset: Option<Arc<dyn BaseToolset>>| async move {
+            match &self.inner {
+                ProviderLlm::ChatCompletions(llm) => {
+                    streamed_flag.store(true, Ordering::Relaxed);
+                    llm.generate_streaming(thread, toolset, deltas).await
+                }
+                inner => inner.base().generate_content(thread, toolset).await,
+            }
+        };
+        let response = match &self.cassette {
+            Some(cassette) => cassette.generate(thread, toolset, call).await?,
+            None => call(thread, toolset).await?,
+        };
+        if !streamed.load(Ordering::Relaxed) {
+            if let Some(text) = response.content().joined_texts().filter(|t| !t.is_empty()) {
+                let _ = deltas.send(text);
+            }
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait::async_trait]
+impl BaseLlm for RequestLlm {
+    fn model_name(&self) -> &str {
+        match &self.inner {
+            ProviderLlm::ChatCompletions(llm) => llm.model_name(),
+            ProviderLlm::Scripted(llm) => llm.model_name(),
+            ProviderLlm::Radkit(llm) => llm.model_name(),
+        }
+    }
+    async fn generate_content(
+        &self,
+        thread: Thread,
+        toolset: Option<Arc<dyn BaseToolset>>,
+    ) -> AgentResult<LlmResponse> {
+        let llm = self.inner.base();
+        match &self.cassette {
+            Some(cassette) => cassette.generate(thread, toolset, |thread, toolset| llm.generate_content(thread, toolset)).await,
+            None => llm.generate_content(thread, toolset).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+    use radkit::models::Thread;
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+    use crate::secret_store::EncryptedFile;
+
+    /// The one request a stub server received.
+    struct Captured {
+        path: String,
+        /// Lowercased header names.
+        headers: HashMap<String, String>,
+        body: Value,
+    }
+
+    /// Accepts a single HTTP request on a free local port and answers it
+    /// with `{}`. Returns the server's URL and the request it receives.
+    async fn stub_server() -> (String, JoinHandle<Captured>) {
+        stub_server_replying("application/json", "{}").await
+    }
+
+    /// Like [`stub_server`], answering with `response` of `content_type`.
+    async fn stub_server_replying(content_type: &'static str, response: &'static str) -> (String, JoinHandle<Captured>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            let path = line.split_whitespace().nth(1).unwrap().to_string();
+
+            let mut headers = HashMap::new();
+            loop {
+                line.clear();
+                reader.read_line(&mut line).await.unwrap();
+                let Some((name, value)) = line.trim_end().split_once(':') else {
+                    break;
+                };
+                headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+            }
+            let length = headers.get("content-length").map_or(0, |l| l.parse().unwrap());
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).await.unwrap();
+
+            let reply = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                content_type,
+                response.len(),
+                response
+            );
+            reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            Captured {
+                path,
+                headers,
+                body: serde_json::from_slice(&body).unwrap(),
+            }
+        });
+        (url, server)
+    }
+
+    /// An empty secret store in a fresh temporary file.
+    fn secrets() -> SecretStore {
+        let path = std::env::temp_dir().join(format!("radkit-secrets-{}.json", uuid::Uuid::new_v4()));
+        SecretStore::EncryptedFile(EncryptedFile::open(path, "test").unwrap())
+    }
+
+    fn config(value: Value) -> LlmConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    /// Sends one message through `llm` and returns the request the stub
+    /// received. The stub's reply is not a valid completion, so the call's
+    /// own result is ignored.
+    async fn send(llm: Arc<dyn BaseLlm>, server: JoinHandle<Captured>) -> Captured {
+        let _ = llm.generate_content(Thread::from_user("Hello"), None).await;
+        server.await.unwrap()
+    }
+
+    /// Builds `config` the way `build_llm` does, with the hosted provider's
+    /// API at `endpoint` on a stub server, and sends one message.
+    async fn capture(config: LlmConfig, secrets: &SecretStore, endpoint: &str) -> Captured {
+        let (url, server) = stub_server().await;
+        let mut config = config;
+        resolve_credential(&mut config, secrets).unwrap();
+        let llm = build_at(config, Some(format!("{}{}", url, endpoint))).unwrap();
+        send(llm.base(), server).await
+    }
+
+    #[tokio::test]
+    async fn openai_sends_model_options_and_bearer_key() {
+        let request = capture(
+            config(json!({ "provider": "OpenAI", "model": "gpt-test", "apiKey": "sk-test", "temperature": 0.5, "maxTokens": 64 })),
+            &secrets(),
+            "/v1/chat/completions",
+        )
+        .await;
+        assert_eq!(request.path, "/v1/chat/completions");
+        assert_eq!(request.headers["authorization"], "Bearer sk-test");
+        assert_eq!(request.body["model"], "gpt-test");
+        assert_eq!(request.body["temperature"], 0.5);
+        assert_eq!(request.body["max_tokens"], 64);
+    }
+
+    #[tokio::test]
+    async fn openai_resolves_a_stored_credential() {
+        let secrets = secrets();
+        secrets.set("openai", "sk-stored").unwrap();
+        let request = capture(
+            config(json!({ "provider": "OpenAI", "model": "gpt-test", "credential": "openai" })),
+            &secrets,
+            "/v1/chat/completions",
+        )
+        .await;
+        assert_eq!(request.headers["authorization"], "Bearer sk-stored");
+    }
+
+    #[tokio::test]
+    async fn openai_sends_every_common_option() {
+        let request = capture(
+            config(json!({
+                "provider": "OpenAI",
+                "model": "gpt-test",
+                "apiKey": "sk-test",
+                "topP": 0.25,
+                "stop": ["END"],
+                "seed": 7,
+                "reasoningEffort": "low",
+                "responseFormat": { "type": "jsonSchema", "name": "answer", "schema": { "type": "object" }, "strict": true },
+                "headers": { "x-test": "1" },
+                "extra": { "presence_penalty": 0.5, "seed": 8 },
+            })),
+            &secrets(),
+            "/v1/chat/completions",
+        )
+        .await;
+        assert_eq!(request.headers["x-test"], "1");
+        assert_eq!(request.body["top_p"], 0.25);
+        assert_eq!(request.body["stop"], json!(["END"]));
+        assert_eq!(request.body["reasoning_effort"], "low");
+        assert_eq!(
+            request.body["response_format"],
+            json!({ "type": "json_schema", "json_schema": { "name": "answer", "schema": { "type": "object" }, "strict": true } })
+        );
+        assert_eq!(request.body["presence_penalty"], 0.5);
+        // `extra` is applied last.
+        assert_eq!(request.body["seed"], 8);
+    }
+
+    #[tokio::test]
+    async fn openai_streams_text_and_assembles_tool_calls() {
+        let (url, server) = stub_server_replying(
+            "text/event-stream",
+            concat!(
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"function\":{\"name\":\"search\",\"arguments\":\"{\\\"q\\\":\"}}]}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"\\\"tauri\\\"}\"}}]}}]}\n\n",
+                "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":2,\"total_tokens\":5}}\n\n",
+                "data: [DONE]\n\n",
+            ),
+        )
+        .await;
+        let llm = build_at(
+            config(json!({ "provider": "OpenAI", "model": "gpt-test", "apiKey": "sk-test" })),
+            Some(format!("{}/v1/chat/completions", url)),
+        )
+        .unwrap();
+        let ProviderLlm::ChatCompletions(llm) = llm else {
+            panic!("expected the chat completions client");
+        };
+
+        let (deltas, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let response = llm.generate_streaming(Thread::from_user("Hello"), None, &deltas).await.unwrap();
+        let request = server.await.unwrap();
+        assert_eq!(request.body["stream"], true);
+
+        drop(deltas);
+        let mut pieces = Vec::new();
+        while let Some(piece) = received.recv().await {
+            pieces.push(piece);
+        }
+        assert_eq!(pieces, ["Hel", "lo"]);
+        assert_eq!(response.content().joined_texts().as_deref(), Some("Hello"));
+        let calls = response.content().tool_calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id(), "call_1");
+        assert_eq!(calls[0].name(), "search");
+        assert_eq!(calls[0].arguments(), &json!({ "q": "tauri" }));
+        assert_eq!(response.usage().total_tokens(), 5);
+    }
+
+    #[tokio::test]
+    async fn anthropic_sends_model_options_and_api_key_header() {
+        let request = capture(
+            config(json!({ "provider": "Anthropic", "model": "claude-test", "apiKey": "sk-ant", "temperature": 0.5, "maxTokens": 64 })),
+            &secrets(),
+            "/v1/messages",
+        )
+        .await;
+        assert_eq!(request.path, "/v1/messages");
+        assert_eq!(request.headers["x-api-key"], "sk-ant");
+        assert!(request.headers.contains_key("anthropic-version"));
+        assert_eq!(request.body["model"], "claude-test");
+        assert_eq!(request.body["temperature"], 0.5);
+        assert_eq!(request.body["max_tokens"], 64);
+    }
+
+    #[tokio::test]
+    async fn gemini_puts_the_model_in_the_path() {
+        let request = capture(
+            config(json!({ "provider": "Gemini", "model": "gemini-test", "apiKey": "g-key", "temperature": 0.5, "maxTokens": 64 })),
+            &secrets(),
+            "/v1beta/",
+        )
+        .await;
+        assert_eq!(request.path, "/v1beta/models/gemini-test:generateContent");
+        assert_eq!(request.headers["x-goog-api-key"], "g-key");
+        assert_eq!(request.body["generationConfig"]["temperature"], 0.5);
+        assert_eq!(request.body["generationConfig"]["maxOutputTokens"], 64);
+    }
+
+    #[tokio::test]
+    async fn openrouter_sends_attribution_headers() {
+        let request = capture(
+            config(json!({
+                "provider": "OpenRouter",
+                "model": "vendor/model",
+                "apiKey": "or-key",
+                "siteUrl": "https://example.com",
+                "appName": "Example",
+                "temperature": 0.5,
+                "maxTokens": 64,
+                "reasoningEffort": "high",
+            })),
+            &secrets(),
+            "/api/v1/chat/completions",
+        )
+        .await;
+        assert_eq!(request.path, "/api/v1/chat/completions");
+        assert_eq!(request.headers["authorization"], "Bearer or-key");
+        assert_eq!(request.headers["http-referer"], "https://example.com");
+        assert_eq!(request.headers["x-title"], "Example");
+        assert_eq!(request.body["model"], "vendor/model");
+        assert_eq!(request.body["reasoning"], json!({ "effort": "high" }));
+        assert!(request.body.get("reasoning_effort").is_none());
+        assert_eq!(request.body["temperature"], 0.5);
+        assert_eq!(request.body["max_tokens"], 64);
+    }
+
+    #[tokio::test]
+    async fn grok_sends_model_options_and_bearer_key() {
+        let request = capture(
+            config(json!({ "provider": "Grok", "model": "grok-test", "apiKey": "xai-key", "temperature": 0.5, "maxTokens": 64 })),
+            &secrets(),
+            "/v1/chat/completions",
+        )
+        .await;
+        assert_eq!(request.path, "/v1/chat/completions");
+        assert_eq!(request.headers["authorization"], "Bearer xai-key");
+        assert_eq!(request.body["model"], "grok-test");
+        assert_eq!(request.body["temperature"], 0.5);
+        assert_eq!(request.body["max_tokens"], 64);
+    }
+
+    #[tokio::test]
+    async fn deepseek_sends_model_options_and_bearer_key() {
+        let request = capture(
+            config(json!({ "provider": "DeepSeek", "model": "deepseek-test", "apiKey": "ds-key", "temperature": 0.5, "maxTokens": 64 })),
+            &secrets(),
+            "/v1/chat/completions",
+        )
+        .await;
+        assert_eq!(request.path, "/v1/chat/completions");
+        assert_eq!(request.headers["authorization"], "Bearer ds-key");
+        assert_eq!(request.body["model"], "deepseek-test");
+        assert_eq!(request.body["temperature"], 0.5);
+        assert_eq!(request.body["max_tokens"], 64);
+    }
+
+    #[tokio::test]
+    async fn openai_compatible_posts_to_chat_completions_under_base_url() {
+        let (url, server) = stub_server().await;
+        let llm = build_llm(
+            config(json!({
+                "provider": "OpenAICompatible",
+                "baseUrl": format!("{}/v1/", url),
+                "model": "llama-test",
+                "apiKey": "local-key",
+                "temperature": 0.5,
+                "maxTokens": 64,
+                "headers": { "x-local": "1" },
+            })),
+            &secrets(),
+        )
+        .unwrap();
+        let request = send(llm, server).await;
+        assert_eq!(request.path, "/v1/chat/completions");
+        assert_eq!(request.headers["authorization"], "Bearer local-key");
+        assert_eq!(request.headers["x-local"], "1");
+        assert_eq!(request.body["model"], "llama-test");
+        assert_eq!(request.body["temperature"], 0.5);
+        assert_eq!(request.body["max_tokens"], 64);
+    }
+
+    #[tokio::test]
+    async fn scripted_replays_its_turns_without_a_server() {
+        let llm = build_llm(
+            config(json!({ "provider": "Scripted", "model": "script", "turns": [{ "text": "Hi there" }] })),
+            &secrets(),
+        )
+        .unwrap();
+        assert_eq!(llm.model_name(), "script");
+        let response = llm.generate_content(Thread::from_user("Hello"), None).await.unwrap();
+        assert_eq!(response.content().joined_texts().as_deref(), Some("Hi there"));
+    }
+
+    #[tokio::test]
+    async fn scripted_overrides_continue_the_script() {
+        let secrets = secrets();
+        let llm = DynamicLlm {
+            slot: Arc::new(
+                LlmSlot::new(
+                    config(json!({ "provider": "Scripted", "turns": [{ "text": "one" }, { "text": "two" }] })),
+                    &secrets,
+                )
+                .unwrap(),
+            ),
+            cassette: None,
+        };
+
+        let (deltas, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let first = llm.for_request(&ChatOptions::default()).unwrap();
+        let response = first.generate(Thread::from_user("Hello"), None, &deltas).await.unwrap();
+        assert_eq!(response.content().joined_texts().as_deref(), Some("one"));
+        // Replies that are not streamed arrive as a single piece.
+        assert_eq!(received.recv().await.as_deref(), Some("one"));
+
+        let options = ChatOptions {
+            model: Some("renamed".into()),
+            ..Default::default()
+        };
+        let second = llm.for_request(&options).unwrap();
+        assert_eq!(second.model_name(), "renamed");
+        let response = second.generate_content(Thread::from_user("Again"), None).await.unwrap();
+        assert_eq!(response.content().joined_texts().as_deref(), Some("two"));
+
+        let options = ChatOptions {
+            temperature: Some(0.5),
+            ..Default::default()
+        };
+        assert!(matches!(llm.for_request(&options), Err(Error::ProviderConfig(_))));
+    }
+
+    fn rejected(value: Value) -> String {
+        match build_llm(config(value), &secrets()) {
+            Err(Error::ProviderConfig(message)) => message,
+            Err(other) => panic!("expected ProviderConfig, got {:?}", other),
+            Ok(_) => panic!("expected the config to be rejected"),
+        }
+    }
+
+    #[test]
+    fn check_options_rejects_seed_on_anthropic() {
+        let message = rejected(json!({ "provider": "Anthropic", "model": "claude-test", "apiKey": "k", "seed": 7 }));
+        assert_eq!(message, "Anthropic does not support seed");
+    }
+
+    #[test]
+    fn check_options_rejects_reasoning_effort_on_gemini() {
+        let message = rejected(json!({ "provider": "Gemini", "model": "gemini-test", "apiKey": "k", "reasoningEffort": "low" }));
+        assert_eq!(message, "Gemini does not support reasoningEffort");
+    }
+
+    #[test]
+    fn check_options_names_every_unsupported_option() {
+        let message = rejected(json!({
+            "provider": "Anthropic",
+            "model": "claude-test",
+            "apiKey": "k",
+            "temperature": 0.5,
+            "topP": 0.9,
+            "stop": ["END"],
+            "responseFormat": { "type": "jsonObject" },
+            "headers": { "x-test": "1" },
+            "extra": { "top_k": 5 },
+        }));
+        assert_eq!(message, "Anthropic does not support topP, stop, responseFormat, headers, extra");
+    }
+
+    #[test]
+    fn check_options_rejects_seed_and_reasoning_effort_on_deepseek() {
+        let message = rejected(json!({
+            "provider": "DeepSeek",
+            "model": "deepseek-test",
+            "apiKey": "k",
+            "topP": 0.9,
+            "seed": 7,
+            "reasoningEffort": "high",
+        }));
+        assert_eq!(message, "DeepSeek does not support seed, reasoningEffort");
+    }
+
+    #[test]
+    fn check_options_accepts_temperature_and_max_tokens() {
+        let common = CommonLlmConfig {
+            temperature: Some(0.5),
+            max_tokens: Some(64),
+            ..Default::default()
+        };
+        assert!(check_options("Gemini", &common, RADKIT_OPTIONS).is_ok());
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use radkit::runtime::context::AuthContext;
+use radkit::runtime::memory::{ContentSource, MemoryContent, MemoryEntry, MemoryService, SearchOptions};
+use radkit::errors::{AgentError, AgentResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::RwLock;
+use crate::models::EmbeddingConfig;
+use crate::secret_store::SecretStore;
+use crate::Error;
+
+const DEFAULT_HASHED_DIMENSIONS: usize = 256;
+const DEFAULT_OPENAI_MODEL: &str = "text-embedding-3-small";
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Turns text into vectors for similarity search.
+pub enum Embedder {
+    /// Feature-hashed bag of words. Needs no network access or API key, but
+    /// only matches on shared words.
+    Hashed { dimensions: usize },
+    /// An OpenAI-compatible `/embeddings` endpoint.
+    OpenAI {
+        client: reqwest::Client,
+        model: String,
+        api_key: String,
+        base_url: String,
+    },
+}
+
+impl Embedder {
+    pub fn from_config(config: EmbeddingConfig, secrets: &SecretStore) -> crate::Result<Self> {
+        match config {
+            EmbeddingConfig::Hashed { dimensions } => Ok(Embedder::Hashed {
+                dimensions: dimensions.unwrap_or(DEFAULT_HASHED_DIMENSIONS).max(1),
+            }),
+            EmbeddingConfig::OpenAI { model, api_key, credential, base_url } => {
+                let api_key = match (api_key, credential) {
+                    (Some(key), _) => key,
+                    (None, Some(_)) if base_url.is_some() => {
+                        return Err(Error::ProviderConfig(
+                            "OpenAI embeddings cannot use a stored credential with a custom baseUrl; pass apiKey instead".into(),
+                        ));
+                    }
+                    (None, Some(name)) => secrets
+                        .get(&name)?
+                        .ok_or_else(|| Error::ProviderConfig(format!("No stored credential named {}", name)))?,
+                    (None, None) => std::env::var("OPENAI_API_KEY").map_err(|_| {
+                        Error::ProviderConfig("OpenAI embeddings need apiKey, credential or OPENAI_API_KEY".into())
+                    })?,
+                };
+                Ok(Embedder::OpenAI {
+                    client: reqwest::Client::new(),
+                    model: model.unwrap_or_else(|| DEFAULT_OPENAI_MODEL.into()),
+                    api_key,
+                    base_url: base_url
+                        .unwrap_or_else(|| DEFAULT_OPENAI_BASE_URL.into())
+                        .trim_end_matches('/')
+                        .to_string(),
+                })
+            }
+        }
+    }
+
+    /// Identifies the vector space, so a stored index built with a different
+    /// embedder is re-embedded instead of compared against.
+    fn id(&self) -> String {
+        match self {
+            Embedder::Hashed { dimensions } => format!("hashed:{}", dimensions),
+            Embedder::OpenAI { model, base_url, .. } => format!("openai:{}:{}", base_url, model),
+        }
+    }
+
+    async fn embed(&self, text: &str) -> AgentResult<Vec<f32>> {
+        match self {
+            Embedder::Hashed { dimensions } => Ok(hashed_embedding(text, *dimensions)),
+            Embedder::OpenAI { client, model, api_key, base_url } => {
+                let response = client
+                    .post(format!("{}/embeddings", base_url))
+                    .bearer_auth(api_key)
+                    .json(&serde_json::json!({ "model": model, "input": text }))
+                    .send()
+                    .await
+                    .map_err(memory_error)?;
+                let status = response.status();
+                let body: Value = response.json().await.map_err(memory_error)?;
+                if !status.is_success() {
+                    return Err(memory_error(format!("Embedding request failed with {}: {}", status, body)));
+                }
+                let vector = body["data"][0]["embedding"]
+                    .as_array()
+                    .ok_or_else(|| memory_error("Embedding response has no data[0].embedding"))?;
+                Ok(vector.iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect())
+            }
+        }
+    }
+}
+
+fn hashed_embedding(text: &str, dimensions: usize) -> Vec<f32> {
+    let mut vector = vec![0.0f32; dimensions];
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        // FNV-1a rather than `DefaultHasher`, whose output may change between
+        // Rust releases and would invalidate the stored index.
+        let hash = word
+            .to_lowercase()
+            .bytes()
+            .fold(0xcbf29ce484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3));
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[(hash % dimensions as u64) as usize] += sign;
+    }
+    vector
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredMemory {
+    id: String,
+    app_name: String,
+    user_name: String,
+    text: String,
+    source: ContentSource,
+    metadata: HashMap<String, Value>,
+    embedding: Vec<f32>,
+}
+
+impl StoredMemory {
+    fn belongs_to(&self, auth: &AuthContext) -> bool {
+        self.app_name == auth.app_name && self.user_name == auth.user_name
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MemoryFile {
+    embedder: String,
+    entries: Vec<StoredMemory>,
+}
+
+/// A `MemoryService` that keeps entries and their embeddings in a JSON file,
+/// rewritten after every change and loaded back on startup. Clones share the
+/// same entries, so a file is only ever written by one index.
+#[derive(Clone)]
+pub struct FileMemoryService {
+    path: PathBuf,
+    index: Arc<RwLock<Index>>,
+}
+
+/// The entries and the embedder their vectors come from, locked together so
+/// switching embedders cannot mix vectors from both.
+struct Index {
+    embedder: Embedder,
+    entries: Vec<StoredMemory>,
+}
+
+/// `entries` with their vectors recomputed by `embedder`.
+async fn reembedded(embedder: &Embedder, entries: &[StoredMemory]) -> AgentResult<Vec<StoredMemory>> {
+    let mut entries = entries.to_vec();
+    for entry in &mut entries {
+        entry.embedding = embedder.embed(&entry.text).await?;
+    }
+    Ok(entries)
+}
+
+impl FileMemoryService {
+    pub async fn open(path: impl Into<PathBuf>, embedder: Embedder) -> crate::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file: MemoryFile = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&raw).map_err(|e| {
+                Error::MemoryBackend(format!("Invalid memory store {}: {}", path.display(), e))
+            })?
+        } else {
+            MemoryFile::default()
+        };
+
+        let reembed = file.embedder != embedder.id() && !file.entries.is_empty();
+        let entries = if reembed {
+            reembedded(&embedder, &file.entries)
+                .await
+                .map_err(|e| Error::MemoryBackend(e.to_string()))?
+        } else {
+            file.entries
+        };
+        let index = Index { embedder, entries };
+
+        let service = Self {
+            path,
+            index: Arc::new(RwLock::new(index)),
+        };
+        if reembed {
+            service
+                .persist(&*service.index.read().await)
+                .map_err(|e| Error::MemoryBackend(e.to_string()))?;
+        }
+        Ok(service)
+    }
+
+    /// Switches to `embedder`, re-embedding the stored entries when it
+    /// produces different vectors than the current one. On failure the
+    /// current embedder is kept.
+    pub async fn set_embedder(&self, embedder: Embedder) -> crate::Result<()> {
+        let mut index = self.index.write().await;
+        if index.embedder.id() != embedder.id() && !index.entries.is_empty() {
+            index.entries = reembedded(&embedder, &index.entries)
+                .await
+                .map_err(|e| Error::MemoryBackend(e.to_string()))?;
+            index.embedder = embedder;
+            self.persist(&index).map_err(|e| Error::MemoryBackend(e.to_string()))?;
+        } else {
+            index.embedder = embedder;
+        }
+        Ok(())
+    }
+
+    fn persist(&self, index: &Index) -> AgentResult<()> {
+        let file = MemoryFile {
+            embedder: index.embedder.id(),
+            entries: index.entries.clone(),
+        };
+        let json = serde_json::to_string(&file).map_err(memory_error)?;
+        // Write beside the store and rename, so a crash mid-write cannot
+        // leave a truncated index behind.
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json).map_err(memory_error)?;
+        std::fs::rename(&tmp, &self.path).map_err(memory_error)
+    }
+}
+
+/// Embeds `content` into an entry owned by `auth`. IDs come from the
+/// content's source, as with radkit's in-memory service, so adding the same
+/// document chunk or conversation message again replaces it.
+async fn stored(embedder: &Embedder, auth: &AuthContext, content: MemoryContent) -> AgentResult<StoredMemory> {
+    Ok(StoredMemory {
+        id: content.source.generate_id(),
+        app_name: auth.app_name.clone(),
+        user_name: auth.user_name.clone(),
+        embedding: embedder.embed(&content.text).await?,
+        text: content.text,
+        source: content.source,
+        metadata: content.metadata,
+    })
+}
+
+fn upsert(entries: &mut Vec<StoredMemory>, memory: StoredMemory) {
+    let existing = entries.iter_mut().find(|entry| {
+        entry.id == memory.id && entry.app_name == memory.app_name && entry.user_name == memory.user_name
+    });
+    match existing {
+        Some(entry) => *entry = memory,
+        None => entries.push(memory),
+    }
+}
+
+#[async_trait::async_trait]
+impl MemoryService for FileMemoryService {
+    async fn add(&self, auth: &AuthContext, content: MemoryContent) -> AgentResult<String> {
+        let mut index = self.index.write().await;
+        let memory = stored(&index.embedder, auth, content).await?;
+        let id = memory.id.clone();
+
+        upsert(&mut index.entries, memory);
+        self.persist(&index)?;
+        Ok(id)
+    }
+
+    async fn add_batch(&self, auth: &AuthContext, contents: Vec<MemoryContent>) -> AgentResult<Vec<String>> {
+        let mut index = self.index.write().await;
+        let mut memories = Vec::with_capacity(contents.len());
+        for content in contents {
+            memories.push(stored(&index.embedder, auth, content).await?);
+        }
+        let ids = memories.iter().map(|memory| memory.id.clone()).collect();
+
+        for memory in memories {
+            upsert(&mut index.entries, memory);
+        }
+        self.persist(&index)?;
+        Ok(ids)
+    }
+
+    async fn search(&self, auth: &AuthContext, query: &str, options: SearchOptions) -> AgentResult<Vec<MemoryEntry>> {
+        let index = self.index.read().await;
+        let query = index.embedder.embed(query).await?;
+        let min_score = options.min_score.unwrap_or(0.0);
+
+        let mut results: Vec<MemoryEntry> = index
+            .entries
+            .iter()
+            .filter(|entry| entry.belongs_to(auth))
+            .filter(|entry| {
+                options
+                    .source_types
+                    .as_ref()
+                    .map_or(true, |types| types.contains(&entry.source.source_type()))
+            })
+            .filter(|entry| {
+                options
+                    .metadata_filter
+                    .as_ref()
+                    .map_or(true, |filter| filter.iter().all(|(key, value)| entry.metadata.get(key) == Some(value)))
+            })
+            .map(|entry| MemoryEntry {
+                id: entry.id.clone(),
+                text: entry.text.clone(),
+                score: cosine_similarity(&query, &entry.embedding),
+                source: entry.source.clone(),
+                metadata: entry.metadata.clone(),
+            })
+            .filter(|entry| entry.score >= min_score)
+            .collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        if let Some(limit) = options.limit {
+            results.truncate(limit);
+        }
+        Ok(results)
+    }
+
+    async fn delete(&self, auth: &AuthContext, id: &str) -> AgentResult<bool> {
+        Ok(self.delete_batch(auth, &[id.to_string()]).await? > 0)
+    }
+
+    async fn delete_batch(&self, auth: &AuthContext, ids: &[String]) -> AgentResult<usize> {
+        let mut index = self.index.write().await;
+        let before = index.entries.len();
+        index.entries.retain(|entry| !(ids.contains(&entry.id) && entry.belongs_to(auth)));
+        let deleted = before - index.entries.len();
+        if deleted > 0 {
+            self.persist(&index)?;
+        }
+        Ok(deleted)
+    }
+}
+
+fn memory_error(e: impl ToString) -> AgentError {
+    AgentError::Internal {
+        component: "FileMemoryService".into(),
+        reason: e.to_string(),
+    }
+}
@@ -1,34 +1,20 @@
 use serde::de::DeserializeOwned;
-use tauri::{
-  plugin::{PluginApi, PluginHandle},
-  AppHandle, Runtime,
-};
+use tauri::{plugin::PluginApi, AppHandle, Runtime};
 
-use crate::models::*;
-
-#[cfg(target_os = "ios")]
-tauri::ios_plugin_binding!(init_plugin_radkit);
-
-// initializes the Kotlin or Swift plugin classes
+// The agent runs entirely in Rust, so there are no Kotlin or Swift plugin
+// classes to register.
 pub fn init<R: Runtime, C: DeserializeOwned>(
-  _app: &AppHandle<R>,
-  api: PluginApi<R, C>,
+  app: &AppHandle<R>,
+  _api: PluginApi<R, C>,
 ) -> crate::Result<Radkit<R>> {
-  #[cfg(target_os = "android")]
-  let handle = api.register_android_plugin("", "ExamplePlugin")?;
-  #[cfg(target_os = "ios")]
-  let handle = api.register_ios_plugin(init_plugin_radkit)?;
-  Ok(Radkit(handle))
+  Ok(Radkit(app.clone()))
 }
 
 /// Access to the radkit APIs.
-pub struct Radkit<R: Runtime>(PluginHandle<R>);
+pub struct Radkit<R: Runtime>(AppHandle<R>);
 
 impl<R: Runtime> Radkit<R> {
-  pub fn ping(&self, payload: PingRequest) -> crate::Result<PingResponse> {
-    self
-      .0
-      .run_mobile_plugin("ping", payload)
-      .map_err(Into::into)
+  pub(crate) fn app(&self) -> &AppHandle<R> {
+    &self.0
   }
 }
@@ -1,6 +1,11 @@
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// How long a frontend tool call waits for its output when neither the tool
+/// nor `InitAgentRequest::tool_timeout_ms` sets a limit.
+pub const DEFAULT_TOOL_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InitAgentRequest {
@@ -8,40 +13,160 @@ pub struct InitAgentRequest {
     pub description: String,
     pub llm: LlmConfig,
     pub tools: Vec<ToolDefinition>,
+    pub cassette: Option<CassetteConfig>,
+    /// Sent as the system message of every conversation. `{{name}}`
+    /// placeholders are filled from `ChatOptions::variables` per request.
+    pub system_prompt: Option<String>,
+    /// Default time to wait for a frontend tool's output before failing the
+    /// call. Defaults to [`DEFAULT_TOOL_TIMEOUT_MS`]; `0` waits indefinitely.
+    pub tool_timeout_ms: Option<u64>,
+    pub task_store: Option<TaskStoreConfig>,
+    pub memory: Option<MemoryStoreConfig>,
+    /// Offers the model the built-in `ask_user` tool, letting it pause a
+    /// task to ask the user a clarifying question.
+    #[serde(default)]
+    pub ask_user: bool,
+}
+
+/// Where the agent keeps tasks and their history.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum TaskStoreConfig {
+    /// Tasks are lost when the app exits.
+    #[default]
+    Memory,
+    /// Tasks are journaled to `path`, defaulting to `radkit/tasks.jsonl` in
+    /// the app data directory.
+    File { path: Option<String> },
+}
+
+/// Where `save_memory` entries and their search index are kept.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum MemoryStoreConfig {
+    /// The runtime's in-memory default; entries are lost when the app exits.
+    #[default]
+    Memory,
+    /// Entries are stored at `path`, defaulting to `radkit/memory.json` in
+    /// the app data directory. `embedding` defaults to `hashed`.
+    File {
+        path: Option<String>,
+        embedding: Option<EmbeddingConfig>,
+    },
+}
+
+/// How memory entries and queries are turned into vectors.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum EmbeddingConfig {
+    /// Local feature hashing; works offline but only matches shared words.
+    Hashed { dimensions: Option<usize> },
+    /// An OpenAI-compatible embeddings endpoint. The key is `api_key`, else
+    /// the stored credential named by `credential`, else `OPENAI_API_KEY`.
+    /// `credential` cannot be combined with `base_url`, so a stored key is
+    /// only ever sent to OpenAI.
+    #[serde(rename = "openai")]
+    OpenAI {
+        model: Option<String>,
+        api_key: Option<String>,
+        credential: Option<String>,
+        base_url: Option<String>,
+    },
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
+pub struct CassetteConfig {
+    pub path: String,
+    pub mode: CassetteMode,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CassetteMode {
+    /// Call the provider and append every exchange to the cassette file.
+    Record,
+    /// Answer from the cassette file without calling the provider.
+    Replay,
+    /// Call the provider and leave the cassette file untouched.
+    Passthrough,
+}
+
+/// Generation options shared by the providers. Anthropic and Gemini accept
+/// only `temperature` and `max_tokens`, and DeepSeek everything but `seed`
+/// and `reasoning_effort`; building an LLM with an option its provider does
+/// not accept fails instead of ignoring it.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct CommonLlmConfig {
     pub temperature: Option<f32>,
     pub max_tokens: Option<u32>,
     pub top_p: Option<f32>,
+    /// Sequences that end generation when produced.
+    pub stop: Option<Vec<String>>,
+    pub seed: Option<u64>,
+    pub reasoning_effort: Option<ReasoningEffort>,
+    pub response_format: Option<ResponseFormat>,
+    /// Sent with every request to the provider.
+    pub headers: Option<HashMap<String, String>>,
+    /// Merged into the request body as-is, after every other field, for
+    /// provider-specific options this config does not cover.
+    pub extra: Option<serde_json::Map<String, Value>>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(tag = "provider", rename_all = "camelCase")]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ResponseFormat {
+    Text,
+    /// Any valid JSON object.
+    JsonObject,
+    /// JSON matching `schema`.
+    JsonSchema {
+        name: String,
+        schema: Value,
+        strict: Option<bool>,
+    },
+}
+
+/// Hosted providers take their key from `api_key`, else from the stored
+/// credential named by `credential`, else from the provider's environment
+/// variable.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "provider", rename_all_fields = "camelCase")]
 pub enum LlmConfig {
     OpenAI {
         model: String,
         api_key: Option<String>,
+        credential: Option<String>,
         #[serde(flatten)]
         common: Option<CommonLlmConfig>,
     },
     Anthropic {
         model: String,
         api_key: Option<String>,
+        credential: Option<String>,
         #[serde(flatten)]
         common: Option<CommonLlmConfig>,
     },
     Gemini {
         model: String,
         api_key: Option<String>,
+        credential: Option<String>,
         #[serde(flatten)]
         common: Option<CommonLlmConfig>,
     },
     OpenRouter {
         model: String,
         api_key: Option<String>,
+        credential: Option<String>,
         site_url: Option<String>,
         app_name: Option<String>,
         #[serde(flatten)]
@@ -50,15 +175,53 @@ pub enum LlmConfig {
     Grok {
         model: String,
         api_key: Option<String>,
+        credential: Option<String>,
         #[serde(flatten)]
         common: Option<CommonLlmConfig>,
     },
     DeepSeek {
         model: String,
         api_key: Option<String>,
+        credential: Option<String>,
         #[serde(flatten)]
         common: Option<CommonLlmConfig>,
     },
+    /// Any server speaking the OpenAI chat completions API, such as Ollama,
+    /// llama.cpp, vLLM or LM Studio. `base_url` includes the version path,
+    /// e.g. `http://localhost:11434/v1`. Local servers usually need no key.
+    /// `credential` is only accepted from the host app, never the frontend.
+    OpenAICompatible {
+        base_url: String,
+        model: String,
+        api_key: Option<String>,
+        credential: Option<String>,
+        #[serde(flatten)]
+        common: Option<CommonLlmConfig>,
+    },
+    /// Replays canned responses instead of calling a hosted provider.
+    /// `turns` takes precedence over `script_path` when both are set.
+    Scripted {
+        model: Option<String>,
+        turns: Option<Vec<ScriptedTurn>>,
+        script_path: Option<String>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptedTurn {
+    pub text: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<ScriptedToolCall>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptedToolCall {
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -67,6 +230,9 @@ pub struct ToolDefinition {
     pub name: String,
     pub description: String,
     pub parameters: Value,
+    /// Overrides `InitAgentRequest::tool_timeout_ms` for this tool; `0`
+    /// waits indefinitely.
+    pub timeout_ms: Option<u64>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -75,6 +241,30 @@ pub struct ToolOutputRequest {
     pub request_id: String,
     pub result: Value,
     pub is_error: bool,
+    /// Pauses the task with this question; the user's reply is returned to
+    /// the model as the tool's result instead of `result`.
+    pub input_required: Option<String>,
+}
+
+/// A frontend tool call awaiting output. Emitted as the
+/// `tool_execution_request` payload and returned by `list_tool_requests`.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolRequestInfo {
+    pub request_id: String,
+    pub name: String,
+    pub args: serde_json::Map<String, Value>,
+    pub task_id: Option<String>,
+    pub context_id: Option<String>,
+    pub tool_call_id: Option<String>,
+    pub sequence: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListToolRequestsRequest {
+    pub task_id: Option<String>,
+    pub context_id: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -83,12 +273,62 @@ pub struct InitResponse {
     pub success: bool,
 }
 
+/// Per-request options, carried to the chat skill in a data part keyed
+/// `chatOptions`.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatOptions {
+    pub variables: Option<HashMap<String, String>>,
+    /// Replaces the configured model for this request, on the same provider.
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// Names of the tools the model may call for this request. All
+    /// registered tools are offered when unset.
+    pub tools: Option<Vec<String>>,
+}
+
+impl ChatOptions {
+    /// Whether the request needs a different LLM than the configured one.
+    pub fn overrides_llm(&self) -> bool {
+        self.model.is_some() || self.temperature.is_some() || self.max_tokens.is_some()
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChatResponse {
     pub task_id: String,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreCredentialRequest {
+    pub name: String,
+    pub secret: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetAuthContextRequest {
+    /// Tenant the user belongs to. Keeps the current app name when unset.
+    pub app_name: Option<String>,
+    pub user_name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthContextInfo {
+    pub app_name: String,
+    pub user_name: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchMemoryRequest {
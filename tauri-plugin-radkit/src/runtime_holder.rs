@@ -1,22 +1,147 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use radkit::runtime::Runtime;
-use radkit::tools::ToolResult;
+use radkit::agent::AgentBuilder;
+use radkit::runtime::{AuthService, Runtime};
+use radkit::runtime::context::AuthContext;
+use radkit::tools::BaseTool;
 use a2a_client::A2AClient;
-use tokio::sync::oneshot;
+use serde_json::Value;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager, Runtime as TauriRuntime};
+use crate::frontend_tool::PendingToolRequests;
+use crate::llm_factory::LlmSlot;
+use crate::memory_store::FileMemoryService;
+use crate::task_store::{FileTaskStore, IdentifiedTaskStore};
+use crate::streams::ActiveStreams;
+use crate::Error;
+use crate::secret_store::{SecretStore, SecretStoreConfig};
+
+pub const DEFAULT_APP_NAME: &str = "radkit-tauri";
+pub const DEFAULT_USER_NAME: &str = "user";
+
+pub const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1:0";
+
+/// Adds a host-registered skill to each agent `init_agent` builds.
+pub type SkillFactory = Box<dyn Fn(AgentBuilder) -> AgentBuilder + Send + Sync>;
+
+/// Settings the host app fixes in Rust through `Builder`.
+pub struct PluginConfig {
+    /// Native tools offered alongside the frontend tools.
+    pub tools: Vec<Arc<dyn BaseTool>>,
+    pub skills: Vec<SkillFactory>,
+    /// Address the embedded A2A server listens on; port 0 picks a free one.
+    pub bind_address: String,
+    /// Default directory for the file-backed task and memory stores.
+    pub data_dir: Option<PathBuf>,
+    /// Whether the frontend may call `init_agent`.
+    pub allow_frontend_init: bool,
+    pub secret_store: Option<SecretStoreConfig>,
+}
+
+impl PluginConfig {
+    /// Directory the file-backed stores default to: the configured one, or
+    /// `radkit` under the app data directory.
+    pub fn data_dir<R: TauriRuntime>(&self, app: &AppHandle<R>) -> crate::Result<PathBuf> {
+        match &self.data_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(app.path().app_data_dir()?.join("radkit")),
+        }
+    }
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            tools: Vec::new(),
+            skills: Vec::new(),
+            bind_address: DEFAULT_BIND_ADDRESS.into(),
+            data_dir: None,
+            allow_frontend_init: true,
+            secret_store: None,
+        }
+    }
+}
 
 pub struct RadkitRuntimeState {
     pub runtime: Mutex<Option<Arc<Runtime>>>,
     pub client: Mutex<Option<A2AClient>>,
-    pub tool_requests: Arc<Mutex<HashMap<String, oneshot::Sender<ToolResult>>>>,
+    /// The running agent's task store, read by `list_tasks`.
+    pub tasks: Mutex<Option<IdentifiedTaskStore>>,
+    /// The running agent's provider, replaced by `update_llm`.
+    pub llm: Mutex<Option<Arc<LlmSlot>>>,
+    /// File-backed stores opened so far, by path. An agent started on the
+    /// same file reuses the open store, since tasks of the agent it replaces
+    /// can still be writing to it.
+    pub task_files: tokio::sync::Mutex<HashMap<PathBuf, FileTaskStore>>,
+    pub memory_files: tokio::sync::Mutex<HashMap<PathBuf, FileMemoryService>>,
+    /// The running agent's A2A server, aborted when `init_agent` starts a
+    /// new one.
+    pub server: Mutex<Option<JoinHandle<()>>>,
+    /// Why the agent set up with `Builder::with_agent` failed to start,
+    /// encoded with [`Error::to_failure`]. Returned by [`Self::not_started`].
+    pub start_failure: Mutex<Option<Value>>,
+    pub tool_requests: PendingToolRequests,
+    pub streams: ActiveStreams,
+    pub config: PluginConfig,
+    pub secrets: SecretStore,
+    /// The active user and tenant. Tasks and memory are scoped to it; the
+    /// agent server reads it through [`ActiveUserAuth`].
+    auth: Arc<Mutex<AuthContext>>,
+}
+
+/// Authenticates every request to the embedded agent server as the active
+/// user, so each user only sees their own tasks and conversations.
+pub struct ActiveUserAuth(Arc<Mutex<AuthContext>>);
+
+impl AuthService for ActiveUserAuth {
+    fn get_auth_context(&self) -> AuthContext {
+        self.0.lock().unwrap().clone()
+    }
 }
 
 impl RadkitRuntimeState {
-    pub fn new() -> Self {
+    pub fn new(config: PluginConfig, secrets: SecretStore) -> Self {
         Self {
             runtime: Mutex::new(None),
             client: Mutex::new(None),
+            tasks: Mutex::new(None),
+            llm: Mutex::new(None),
+            task_files: tokio::sync::Mutex::new(HashMap::new()),
+            memory_files: tokio::sync::Mutex::new(HashMap::new()),
+            server: Mutex::new(None),
+            start_failure: Mutex::new(None),
             tool_requests: Arc::new(Mutex::new(HashMap::new())),
+            streams: Arc::new(Mutex::new(HashMap::new())),
+            config,
+            secrets,
+            auth: Arc::new(Mutex::new(AuthContext {
+                app_name: DEFAULT_APP_NAME.into(),
+                user_name: DEFAULT_USER_NAME.into(),
+            })),
+        }
+    }
+
+    /// The error for a request that needs an agent when none is running:
+    /// why the host app's agent failed to start, if it did, else
+    /// `NotInitialized`.
+    pub fn not_started(&self) -> Error {
+        match &*self.start_failure.lock().unwrap() {
+            Some(failure) => Error::from_failure(failure),
+            None => Error::NotInitialized,
         }
     }
+
+    pub fn auth_context(&self) -> AuthContext {
+        self.auth.lock().unwrap().clone()
+    }
+
+    pub fn set_auth_context(&self, auth: AuthContext) {
+        *self.auth.lock().unwrap() = auth;
+    }
+
+    /// The auth service the agent server is built with.
+    pub fn auth_service(&self) -> ActiveUserAuth {
+        ActiveUserAuth(self.auth.clone())
+    }
 }
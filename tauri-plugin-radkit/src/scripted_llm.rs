@@ -0,0 +1,125 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use radkit::models::{BaseLlm, Content, ContentPart, LlmResponse, Thread, TokenUsage};
+use radkit::tools::{BaseToolset, ToolCall};
+use radkit::errors::{AgentError, AgentResult};
+use crate::models::ScriptedTurn;
+use crate::Error;
+
+/// A `BaseLlm` that replays canned turns in order instead of calling a provider.
+pub struct ScriptedLlm {
+    model: String,
+    turns: Arc<Vec<ScriptedTurn>>,
+    cursor: Arc<AtomicUsize>,
+}
+
+impl ScriptedLlm {
+    pub fn new(model: String, turns: Vec<ScriptedTurn>) -> Self {
+        Self {
+            model,
+            turns: Arc::new(turns),
+            cursor: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// The same script under another model name. Both share one position,
+    /// so turns are not replayed.
+    pub fn renamed(&self, model: String) -> Self {
+        Self {
+            model,
+            turns: self.turns.clone(),
+            cursor: self.cursor.clone(),
+        }
+    }
+
+    /// Loads a script from a JSON file containing an array of turns.
+    pub fn from_file(model: String, path: impl AsRef<Path>) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| Error::ProviderConfig(format!("Failed to read script {}: {}", path.display(), e)))?;
+        let turns: Vec<ScriptedTurn> = serde_json::from_str(&raw)
+            .map_err(|e| Error::ProviderConfig(format!("Invalid script {}: {}", path.display(), e)))?;
+        Ok(Self::new(model, turns))
+    }
+
+    fn next_turn(&self) -> Option<(usize, &ScriptedTurn)> {
+        let index = self.cursor.fetch_add(1, Ordering::SeqCst);
+        self.turns.get(index).map(|turn| (index, turn))
+    }
+}
+
+#[async_trait::async_trait]
+impl BaseLlm for ScriptedLlm {
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    async fn generate_content(
+        &self,
+        _thread: Thread,
+        _toolset: Option<Arc<dyn BaseToolset>>,
+    ) -> AgentResult<LlmResponse> {
+        let (index, turn) = self.next_turn().ok_or_else(|| AgentError::Internal {
+            component: "ScriptedLlm".into(),
+            reason: format!("Script exhausted after {} turns", self.turns.len()),
+        })?;
+
+        let mut parts = Vec::new();
+        if let Some(text) = &turn.text {
+            parts.push(ContentPart::Text(text.clone()));
+        }
+        for (i, call) in turn.tool_calls.iter().enumerate() {
+            let id = call.id.clone().unwrap_or_else(|| format!("call_{}_{}", index, i));
+            parts.push(ContentPart::ToolCall(ToolCall::new(id, call.name.clone(), call.arguments.clone())));
+        }
+
+        Ok(LlmResponse::new(Content::from_parts(parts), TokenUsage::empty()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn script(turns: serde_json::Value) -> ScriptedLlm {
+        ScriptedLlm::new("scripted".into(), serde_json::from_value(turns).unwrap())
+    }
+
+    async fn next(llm: &ScriptedLlm) -> AgentResult<LlmResponse> {
+        llm.generate_content(Thread::from_user("Hello"), None).await
+    }
+
+    #[tokio::test]
+    async fn tool_call_turns_are_replayed_with_ids() {
+        let llm = script(json!([
+            { "toolCalls": [
+                { "id": "lookup-1", "name": "lookup", "arguments": { "city": "Oslo" } },
+                { "name": "clock" }
+            ] },
+            { "text": "It is sunny in Oslo." }
+        ]));
+
+        let first = next(&llm).await.unwrap();
+        let calls = first.content().tool_calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!((calls[0].id(), calls[0].name()), ("lookup-1", "lookup"));
+        assert_eq!(calls[0].arguments(), &json!({ "city": "Oslo" }));
+        assert_eq!((calls[1].id(), calls[1].name()), ("call_0_1", "clock"));
+        assert_eq!(calls[1].arguments(), &serde_json::Value::Null);
+        assert!(first.content().first_text().is_none());
+
+        let second = next(&llm).await.unwrap();
+        assert_eq!(second.content().first_text(), Some("It is sunny in Oslo."));
+        assert!(second.content().tool_calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn exhausted_script_is_an_error() {
+        let llm = script(json!([{ "text": "Only turn" }]));
+        next(&llm).await.unwrap();
+        let error = next(&llm).await.unwrap_err();
+        assert!(error.to_string().contains("Script exhausted after 1 turns"), "{}", error);
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chacha20poly1305::aead::rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Runtime};
+use crate::runtime_holder::PluginConfig;
+use crate::Error;
+
+/// Environment variable that, when set, selects the encrypted-file store by
+/// default. Meant for headless machines without a keyring, such as CI.
+pub const PASSPHRASE_ENV: &str = "RADKIT_SECRETS_PASSPHRASE";
+
+/// Where provider credentials are kept, chosen by the host app through
+/// `Builder::secret_store`.
+#[derive(Debug, Clone)]
+pub enum SecretStoreConfig {
+    /// The OS keyring (Keychain, Credential Manager, Secret Service).
+    /// `service` defaults to the app identifier.
+    Keyring { service: Option<String> },
+    /// A file encrypted with a key derived from `passphrase`. `path`
+    /// defaults to `secrets.json` in the plugin's data directory.
+    EncryptedFile { path: Option<PathBuf>, passphrase: String },
+}
+
+/// Stores provider credentials by name, so API keys never have to be held
+/// by the frontend.
+pub enum SecretStore {
+    #[cfg(desktop)]
+    Keyring { service: String },
+    EncryptedFile(EncryptedFile),
+    /// No store is configured, e.g. on mobile without a passphrase.
+    #[cfg(mobile)]
+    Unavailable,
+}
+
+impl SecretStore {
+    /// Opens the configured store. Without one, an encrypted file is used if
+    /// `RADKIT_SECRETS_PASSPHRASE` is set, else the OS keyring on desktop.
+    pub fn open<R: Runtime>(app: &AppHandle<R>, config: &PluginConfig) -> crate::Result<Self> {
+        let default_path = || config.data_dir(app).map(|dir| dir.join("secrets.json"));
+        let store_config = match &config.secret_store {
+            Some(store_config) => Some(store_config.clone()),
+            None => std::env::var(PASSPHRASE_ENV)
+                .ok()
+                .map(|passphrase| SecretStoreConfig::EncryptedFile { path: None, passphrase }),
+        };
+
+        match store_config {
+            Some(SecretStoreConfig::EncryptedFile { path, passphrase }) => {
+                let path = match path {
+                    Some(path) => path,
+                    None => default_path()?,
+                };
+                Ok(SecretStore::EncryptedFile(EncryptedFile::open(path, &passphrase)?))
+            }
+            #[cfg(desktop)]
+            Some(SecretStoreConfig::Keyring { service }) => Ok(SecretStore::Keyring {
+                service: service.unwrap_or_else(|| app.config().identifier.clone()),
+            }),
+            #[cfg(mobile)]
+            Some(SecretStoreConfig::Keyring { .. }) => Ok(SecretStore::Unavailable),
+            #[cfg(desktop)]
+            None => Ok(SecretStore::Keyring {
+                service: app.config().identifier.clone(),
+            }),
+            #[cfg(mobile)]
+            None => Ok(SecretStore::Unavailable),
+        }
+    }
+
+    pub fn set(&self, name: &str, secret: &str) -> crate::Result<()> {
+        match self {
+            #[cfg(desktop)]
+            SecretStore::Keyring { service } => keyring::Entry::new(service, name)
+                .and_then(|entry| entry.set_password(secret))
+                .map_err(secret_error),
+            SecretStore::EncryptedFile(file) => file.set(name, secret),
+            #[cfg(mobile)]
+            SecretStore::Unavailable => Err(unavailable()),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> crate::Result<Option<String>> {
+        match self {
+            #[cfg(desktop)]
+            SecretStore::Keyring { service } => {
+                match keyring::Entry::new(service, name).and_then(|entry| entry.get_password()) {
+                    Ok(secret) => Ok(Some(secret)),
+                    Err(keyring::Error::NoEntry) => Ok(None),
+                    Err(e) => Err(secret_error(e)),
+                }
+            }
+            SecretStore::EncryptedFile(file) => file.get(name),
+            #[cfg(mobile)]
+            SecretStore::Unavailable => Err(unavailable()),
+        }
+    }
+
+    /// Returns whether a credential was removed.
+    pub fn delete(&self, name: &str) -> crate::Result<bool> {
+        match self {
+            #[cfg(desktop)]
+            SecretStore::Keyring { service } => {
+                match keyring::Entry::new(service, name).and_then(|entry| entry.delete_credential()) {
+                    Ok(()) => Ok(true),
+                    Err(keyring::Error::NoEntry) => Ok(false),
+                    Err(e) => Err(secret_error(e)),
+                }
+            }
+            SecretStore::EncryptedFile(file) => file.delete(name),
+            #[cfg(mobile)]
+            SecretStore::Unavailable => Err(unavailable()),
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct SecretsFile {
+    salt: Vec<u8>,
+    /// [`VERIFIER`] sealed under the file's key, checked on open. Missing in
+    /// files written by earlier versions until they are next saved.
+    #[serde(default)]
+    verifier: Option<SealedSecret>,
+    entries: HashMap<String, SealedSecret>,
+}
+
+/// Plaintext of the verifier that tells a wrong passphrase apart on open.
+const VERIFIER: &[u8] = b"radkit-secrets";
+
+#[derive(Serialize, Deserialize)]
+struct SealedSecret {
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+/// Secrets sealed with ChaCha20-Poly1305 under an Argon2-derived key. The
+/// whole file is rewritten on every change.
+pub struct EncryptedFile {
+    path: PathBuf,
+    cipher: ChaCha20Poly1305,
+    file: Mutex<SecretsFile>,
+}
+
+impl EncryptedFile {
+    pub fn open(path: impl Into<PathBuf>, passphrase: &str) -> crate::Result<Self> {
+        let path = path.into();
+        let file = if path.exists() {
+            let raw = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&raw).map_err(|e| {
+                Error::SecretStore(format!("Invalid secrets file {}: {}", path.display(), e))
+            })?
+        } else {
+            let mut salt = vec![0u8; 16];
+            OsRng.fill_bytes(&mut salt);
+            SecretsFile {
+                salt,
+                verifier: None,
+                entries: HashMap::new(),
+            }
+        };
+
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &file.salt, &mut key)
+            .map_err(secret_error)?;
+        let store = Self {
+            path,
+            cipher: ChaCha20Poly1305::new(&key),
+            file: Mutex::new(file),
+        };
+
+        let mut file = store.file.lock().unwrap();
+        // Files without a verifier are checked against any entry instead,
+        // then given one.
+        let check = file.verifier.as_ref().or_else(|| file.entries.values().next());
+        if let Some(sealed) = check {
+            if store.unseal(sealed).is_none() {
+                return Err(Error::SecretStore(format!(
+                    "Wrong passphrase for secrets file {}",
+                    store.path.display()
+                )));
+            }
+        }
+        if file.verifier.is_none() {
+            file.verifier = Some(store.seal(VERIFIER)?);
+            if !file.entries.is_empty() {
+                store.persist(&file)?;
+            }
+        }
+        drop(file);
+        Ok(store)
+    }
+
+    fn seal(&self, plaintext: &[u8]) -> crate::Result<SealedSecret> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, plaintext).map_err(secret_error)?;
+        Ok(SealedSecret {
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+
+    /// The plaintext of `sealed`, or `None` if it was not sealed with this
+    /// key or is damaged.
+    fn unseal(&self, sealed: &SealedSecret) -> Option<Vec<u8>> {
+        if sealed.nonce.len() != 12 {
+            return None;
+        }
+        self.cipher
+            .decrypt(Nonce::from_slice(&sealed.nonce), sealed.ciphertext.as_slice())
+            .ok()
+    }
+
+    fn set(&self, name: &str, secret: &str) -> crate::Result<()> {
+        let sealed = self.seal(secret.as_bytes())?;
+        let mut file = self.file.lock().unwrap();
+        file.entries.insert(name.to_string(), sealed);
+        self.persist(&file)
+    }
+
+    fn get(&self, name: &str) -> crate::Result<Option<String>> {
+        let file = self.file.lock().unwrap();
+        let Some(sealed) = file.entries.get(name) else {
+            return Ok(None);
+        };
+        // The passphrase was checked on open, so this entry is damaged.
+        let plaintext = self
+            .unseal(sealed)
+            .ok_or_else(|| Error::SecretStore(format!("Corrupt entry {}", name)))?;
+        String::from_utf8(plaintext).map(Some).map_err(secret_error)
+    }
+
+    fn delete(&self, name: &str) -> crate::Result<bool> {
+        let mut file = self.file.lock().unwrap();
+        if file.entries.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.persist(&file)?;
+        Ok(true)
+    }
+
+    fn persist(&self, file: &SecretsFile) -> crate::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string(file)?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(mobile)]
+fn unavailable() -> Error {
+    Error::SecretStore("No secret store is configured on this platform".into())
+}
+
+fn secret_error(e: impl ToString) -> Error {
+    Error::SecretStore(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh secrets file path in the temporary directory.
+    fn secrets_path() -> PathBuf {
+        std::env::temp_dir().join(format!("radkit-secrets-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn secrets_survive_reopening_with_the_passphrase() {
+        let path = secrets_path();
+        let file = EncryptedFile::open(&path, "correct horse").unwrap();
+        file.set("openai", "sk-test").unwrap();
+        file.set("anthropic", "sk-ant").unwrap();
+        assert!(file.delete("anthropic").unwrap());
+        drop(file);
+
+        let file = EncryptedFile::open(&path, "correct horse").unwrap();
+        assert_eq!(file.get("openai").unwrap().as_deref(), Some("sk-test"));
+        assert_eq!(file.get("anthropic").unwrap(), None);
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("sk-test"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected_on_open() {
+        let path = secrets_path();
+        EncryptedFile::open(&path, "correct horse").unwrap().set("openai", "sk-test").unwrap();
+
+        let error = EncryptedFile::open(&path, "battery staple").err().unwrap();
+        assert!(matches!(error, Error::SecretStore(_)));
+        assert!(error.to_string().contains("Wrong passphrase"), "{}", error);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn emptied_file_still_checks_the_passphrase() {
+        let path = secrets_path();
+        let file = EncryptedFile::open(&path, "correct horse").unwrap();
+        file.set("openai", "sk-test").unwrap();
+        file.delete("openai").unwrap();
+        drop(file);
+
+        assert!(EncryptedFile::open(&path, "battery staple").is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
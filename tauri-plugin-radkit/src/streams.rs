@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::async_runtime::JoinHandle;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::{mpsc, watch};
+use crate::Error;
+
+/// A `stream_chat` forwarding task that `stop_stream` can abort.
+pub struct ActiveStream {
+    pub handle: JoinHandle<()>,
+    /// The A2A task the stream belongs to, filled in from the first event
+    /// that names it.
+    pub task_id: watch::Receiver<Option<String>>,
+}
+
+pub type ActiveStreams = Arc<Mutex<HashMap<String, ActiveStream>>>;
+
+/// Everything a stream reports, in order: `Started`, any number of `Event`s,
+/// then `Completed`. A stream that fails ends with `Error` instead, which can
+/// arrive before `Started` if it fails before its first event. Stopped
+/// streams report nothing further.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum StreamMessage {
+    Started {
+        stream_id: String,
+        task_id: Option<String>,
+        context_id: Option<String>,
+    },
+    Event {
+        stream_id: String,
+        event: Value,
+    },
+    Completed {
+        stream_id: String,
+        task_id: Option<String>,
+        context_id: Option<String>,
+        /// Last task state the stream reported, e.g. `completed` or
+        /// `input-required`.
+        state: Option<String>,
+    },
+    Error {
+        stream_id: String,
+        task_id: Option<String>,
+        error: Error,
+    },
+}
+
+impl StreamMessage {
+    fn event_name(&self) -> &'static str {
+        match self {
+            StreamMessage::Started { .. } => "stream_started",
+            StreamMessage::Event { .. } => "stream_event",
+            StreamMessage::Completed { .. } => "stream_completed",
+            StreamMessage::Error { .. } => "stream_error",
+        }
+    }
+}
+
+/// Messages a Rust caller of `Radkit::stream_chat` can leave unread before
+/// the stream waits for it.
+pub const STREAM_BUFFER: usize = 64;
+
+/// Where a stream's messages go, in order.
+///
+/// Only `Sender` applies back-pressure: the stream stops reading events while
+/// its buffer is full. Webview IPC has no way to report that the frontend is
+/// behind, so `Channel` and `Broadcast` never block the stream.
+pub enum StreamSink {
+    /// The channel the frontend passed to `stream_chat`.
+    Channel(Channel<StreamMessage>),
+    /// A Rust caller of `Radkit::stream_chat`.
+    Sender(mpsc::Sender<StreamMessage>),
+    /// Every window, as the matching `stream_*` event.
+    Broadcast,
+}
+
+impl StreamSink {
+    pub async fn deliver<R: Runtime>(&self, app: &AppHandle<R>, message: StreamMessage) {
+        match self {
+            StreamSink::Channel(channel) => {
+                let _ = channel.send(message);
+            }
+            StreamSink::Sender(sender) => {
+                let _ = sender.send(message).await;
+            }
+            StreamSink::Broadcast => {
+                // `emit` needs a `Clone` payload, which `Error` is not.
+                if let Ok(payload) = serde_json::to_value(&message) {
+                    let _ = app.emit(message.event_name(), payload);
+                }
+            }
+        }
+    }
+}
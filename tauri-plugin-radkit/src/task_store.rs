@@ -0,0 +1,669 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use radkit::runtime::context::{AuthContext, SessionState, TaskState};
+use radkit::runtime::task_manager::{Task, TaskEvent, TaskStore};
+use radkit::runtime::InMemoryTaskStore;
+use radkit::errors::{AgentError, AgentResult};
+use a2a_types::{Artifact, Message, Part, TaskArtifactUpdateEvent, TaskStatus, TaskStatusUpdateEvent};
+use a2a_types::TaskState as A2ATaskState;
+use serde::{Deserialize, Serialize};
+use crate::Error;
+use crate::chat_skill::{CONTEXT_ID_KEY, TASK_ID_KEY, TEXT_DELTA_TYPE};
+
+/// One line of the journal: a mutation applied to the task store.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase", rename_all_fields = "camelCase")]
+enum Record {
+    SaveTask { app_name: String, user_name: String, task: StoredTask },
+    AppendEvent { app_name: String, user_name: String, task_key: String, event: StoredEvent },
+    SaveTaskState { app_name: String, user_name: String, task_id: String, state: TaskState },
+    SetTaskSkill { app_name: String, user_name: String, task_id: String, skill_id: String },
+    SaveSessionState { app_name: String, user_name: String, context_id: String, state: SessionState },
+}
+
+impl Record {
+    fn auth(&self) -> AuthContext {
+        let (Record::SaveTask { app_name, user_name, .. }
+        | Record::AppendEvent { app_name, user_name, .. }
+        | Record::SaveTaskState { app_name, user_name, .. }
+        | Record::SetTaskSkill { app_name, user_name, .. }
+        | Record::SaveSessionState { app_name, user_name, .. }) = self;
+        AuthContext {
+            app_name: app_name.clone(),
+            user_name: user_name.clone(),
+        }
+    }
+
+    /// What the record sets. A later record with the same key makes this one
+    /// redundant; events have no key since each one is kept.
+    fn key(&self) -> Option<String> {
+        let auth = self.auth();
+        let (kind, id) = match self {
+            Record::SaveTask { task, .. } => ("task", &task.id),
+            Record::AppendEvent { .. } => return None,
+            Record::SaveTaskState { task_id, .. } => ("taskState", task_id),
+            Record::SetTaskSkill { task_id, .. } => ("taskSkill", task_id),
+            Record::SaveSessionState { context_id, .. } => ("session", context_id),
+        };
+        Some(format!("{}:{}:{}:{}", kind, auth.app_name, auth.user_name, id))
+    }
+
+    async fn apply(self, store: &InMemoryTaskStore) -> AgentResult<()> {
+        let auth = self.auth();
+        match self {
+            Record::SaveTask { task, .. } => store.save_task(&auth, &task.into()).await,
+            Record::AppendEvent { task_key, event, .. } => store.append_event(&auth, &task_key, &event.into()).await,
+            Record::SaveTaskState { task_id, state, .. } => store.save_task_state(&auth, &task_id, &state).await,
+            Record::SetTaskSkill { task_id, skill_id, .. } => store.set_task_skill(&auth, &task_id, &skill_id).await,
+            Record::SaveSessionState { context_id, state, .. } => {
+                store.save_session_state(&auth, &context_id, &state).await
+            }
+        }
+    }
+}
+
+/// Serializable form of radkit's `Task`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredTask {
+    id: String,
+    context_id: String,
+    status: TaskStatus,
+    artifacts: Vec<Artifact>,
+}
+
+impl From<&Task> for StoredTask {
+    fn from(task: &Task) -> Self {
+        Self {
+            id: task.id.clone(),
+            context_id: task.context_id.clone(),
+            status: task.status.clone(),
+            artifacts: task.artifacts.clone(),
+        }
+    }
+}
+
+impl From<StoredTask> for Task {
+    fn from(task: StoredTask) -> Self {
+        Self {
+            id: task.id,
+            context_id: task.context_id,
+            status: task.status,
+            artifacts: task.artifacts,
+        }
+    }
+}
+
+/// Serializable form of radkit's `TaskEvent`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum StoredEvent {
+    StatusUpdate(TaskStatusUpdateEvent),
+    ArtifactUpdate(TaskArtifactUpdateEvent),
+    Message(Message),
+}
+
+impl From<&TaskEvent> for StoredEvent {
+    fn from(event: &TaskEvent) -> Self {
+        match event {
+            TaskEvent::StatusUpdate(update) => StoredEvent::StatusUpdate(update.clone()),
+            TaskEvent::ArtifactUpdate(update) => StoredEvent::ArtifactUpdate(update.clone()),
+            TaskEvent::Message(message) => StoredEvent::Message(message.clone()),
+        }
+    }
+}
+
+impl From<StoredEvent> for TaskEvent {
+    fn from(event: StoredEvent) -> Self {
+        match event {
+            StoredEvent::StatusUpdate(update) => TaskEvent::StatusUpdate(update),
+            StoredEvent::ArtifactUpdate(update) => TaskEvent::ArtifactUpdate(update),
+            StoredEvent::Message(message) => TaskEvent::Message(message),
+        }
+    }
+}
+
+/// A `TaskStore` that keeps tasks in memory and appends every change to a
+/// JSON-lines journal, which is replayed on startup.
+///
+/// Opening the store also compacts the journal, rewriting it with only the
+/// latest version of each task and state plus every event, so it grows with
+/// the tasks kept rather than with every status change. A journal must only
+/// be opened once at a time: compaction replaces the file under any other
+/// store writing to it. Clones share the same store and journal.
+#[derive(Clone)]
+pub struct FileTaskStore {
+    inner: Arc<InMemoryTaskStore>,
+    path: PathBuf,
+    journal: Arc<Mutex<File>>,
+}
+
+impl FileTaskStore {
+    pub async fn open(path: impl Into<PathBuf>) -> crate::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let inner = InMemoryTaskStore::new();
+        if path.exists() {
+            let records = replay(&inner, &path).await?;
+            compact(&path, &records)?;
+        }
+
+        let journal = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            inner: Arc::new(inner),
+            path,
+            journal: Arc::new(Mutex::new(journal)),
+        })
+    }
+
+    fn append(&self, record: &Record) -> AgentResult<()> {
+        let line = serde_json::to_string(record).map_err(|e| self.journal_error(e))?;
+        let mut journal = self.journal.lock().unwrap();
+        writeln!(journal, "{}", line)
+            .and_then(|_| journal.flush())
+            .map_err(|e| self.journal_error(e))
+    }
+
+    fn journal_error(&self, e: impl ToString) -> AgentError {
+        AgentError::Internal {
+            component: "FileTaskStore".into(),
+            reason: format!("{}: {}", self.path.display(), e.to_string()),
+        }
+    }
+}
+
+/// Applies the journal at `path` to `inner`, returning the records still
+/// needed to rebuild it.
+async fn replay(inner: &InMemoryTaskStore, path: &Path) -> crate::Result<Vec<Record>> {
+    let mut records: Vec<Option<Record>> = Vec::new();
+    let mut latest: HashMap<String, usize> = HashMap::new();
+
+    // Read leniently: a truncated last line may end partway through a
+    // multi-byte character.
+    let contents = String::from_utf8_lossy(&std::fs::read(path)?).into_owned();
+    let lines: Vec<&str> = contents.lines().collect();
+    let last = lines.iter().rposition(|line| !line.trim().is_empty());
+    for (number, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record = match serde_json::from_str(line) {
+            Ok(record) => record,
+            // A crash mid-write can leave a truncated last line; skip it
+            // rather than refusing to start. Compaction then drops it from
+            // the file. Anywhere else, the journal is damaged.
+            Err(e) if Some(number) == last => {
+                eprintln!("Skipping truncated task journal line {} in {}: {}", number + 1, path.display(), e);
+                continue;
+            }
+            Err(e) => {
+                return Err(Error::Agent {
+                    component: "FileTaskStore".into(),
+                    reason: format!("Unreadable line {} in {}: {}", number + 1, path.display(), e),
+                })
+            }
+        };
+
+        let key = record.key();
+        record.clone().apply(inner).await.map_err(|e| Error::Agent {
+            component: "FileTaskStore".into(),
+            reason: e.to_string(),
+        })?;
+
+        if let Some(key) = key {
+            if let Some(previous) = latest.insert(key, records.len()) {
+                records[previous] = None;
+            }
+        }
+        records.push(Some(record));
+    }
+    Ok(records.into_iter().flatten().collect())
+}
+
+/// Rewrites the journal at `path` with only `records`. The new journal is
+/// written beside the old one and renamed over it, so a crash leaves one or
+/// the other intact.
+fn compact(path: &Path, records: &[Record]) -> crate::Result<()> {
+    let tmp = path.with_extension("jsonl.tmp");
+    {
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        for record in records {
+            serde_json::to_writer(&mut writer, record)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl TaskStore for FileTaskStore {
+    async fn get_task(&self, auth: &AuthContext, task_id: &str) -> AgentResult<Option<Task>> {
+        self.inner.get_task(auth, task_id).await
+    }
+
+    async fn list_tasks(&self, auth: &AuthContext) -> AgentResult<Vec<Task>> {
+        self.inner.list_tasks(auth).await
+    }
+
+    async fn save_task(&self, auth: &AuthContext, task: &Task) -> AgentResult<()> {
+        self.inner.save_task(auth, task).await?;
+        self.append(&Record::SaveTask {
+            app_name: auth.app_name.clone(),
+            user_name: auth.user_name.clone(),
+            task: task.into(),
+        })
+    }
+
+    async fn append_event(&self, auth: &AuthContext, task_key: &str, event: &TaskEvent) -> AgentResult<()> {
+        self.inner.append_event(auth, task_key, event).await?;
+        self.append(&Record::AppendEvent {
+            app_name: auth.app_name.clone(),
+            user_name: auth.user_name.clone(),
+            task_key: task_key.to_string(),
+            event: event.into(),
+        })
+    }
+
+    async fn get_events(&self, auth: &AuthContext, task_key: &str) -> AgentResult<Vec<TaskEvent>> {
+        self.inner.get_events(auth, task_key).await
+    }
+
+    async fn list_event_task_keys(&self, auth: &AuthContext) -> AgentResult<Vec<String>> {
+        self.inner.list_event_task_keys(auth).await
+    }
+
+    async fn list_task_ids(&self, auth: &AuthContext) -> AgentResult<Vec<String>> {
+        self.inner.list_task_ids(auth).await
+    }
+
+    async fn list_context_ids(&self, auth: &AuthContext) -> AgentResult<Vec<String>> {
+        self.inner.list_context_ids(auth).await
+    }
+
+    async fn save_task_state(&self, auth: &AuthContext, task_id: &str, state: &TaskState) -> AgentResult<()> {
+        self.inner.save_task_state(auth, task_id, state).await?;
+        self.append(&Record::SaveTaskState {
+            app_name: auth.app_name.clone(),
+            user_name: auth.user_name.clone(),
+            task_id: task_id.to_string(),
+            state: state.clone(),
+        })
+    }
+
+    async fn load_task_state(&self, auth: &AuthContext, task_id: &str) -> AgentResult<Option<TaskState>> {
+        self.inner.load_task_state(auth, task_id).await
+    }
+
+    async fn set_task_skill(&self, auth: &AuthContext, task_id: &str, skill_id: &str) -> AgentResult<()> {
+        self.inner.set_task_skill(auth, task_id, skill_id).await?;
+        self.append(&Record::SetTaskSkill {
+            app_name: auth.app_name.clone(),
+            user_name: auth.user_name.clone(),
+            task_id: task_id.to_string(),
+            skill_id: skill_id.to_string(),
+        })
+    }
+
+    async fn get_task_skill(&self, auth: &AuthContext, task_id: &str) -> AgentResult<Option<String>> {
+        self.inner.get_task_skill(auth, task_id).await
+    }
+
+    async fn save_session_state(&self, auth: &AuthContext, context_id: &str, state: &SessionState) -> AgentResult<()> {
+        self.inner.save_session_state(auth, context_id, state).await?;
+        self.append(&Record::SaveSessionState {
+            app_name: auth.app_name.clone(),
+            user_name: auth.user_name.clone(),
+            context_id: context_id.to_string(),
+            state: state.clone(),
+        })
+    }
+
+    async fn load_session_state(&self, auth: &AuthContext, context_id: &str) -> AgentResult<Option<SessionState>> {
+        self.inner.load_session_state(auth, context_id).await
+    }
+}
+
+/// Wraps a `TaskStore` so skills can tell which task and context they are
+/// running in: radkit hands skills only their state, so the IDs are added to
+/// the session state the executor loads for each run, under
+/// [`TASK_ID_KEY`] and [`CONTEXT_ID_KEY`], and removed again before saving.
+///
+/// The task is the one set to `working` in the context, which the executor
+/// does right before running a skill. Only one task can run in a context at a
+/// time: setting a second one to `working` fails with `InvalidInput` until
+/// the first one finishes, pauses for input or is cancelled, so tool calls
+/// are never attributed to the wrong task.
+///
+/// Clones share the same store, so the plugin can read tasks the runtime
+/// keeps.
+#[derive(Clone)]
+pub struct IdentifiedTaskStore {
+    inner: Arc<dyn TaskStore>,
+    running: Arc<Mutex<HashMap<String, String>>>,
+    cancelled: CancelledTasks,
+}
+
+/// IDs of the tasks cancelled through [`IdentifiedTaskStore::cancel`],
+/// shared with the skill so it can stop working on them.
+pub type CancelledTasks = Arc<Mutex<HashSet<String>>>;
+
+impl IdentifiedTaskStore {
+    pub fn new(inner: impl TaskStore + 'static, cancelled: CancelledTasks) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            running: Arc::new(Mutex::new(HashMap::new())),
+            cancelled,
+        }
+    }
+
+    /// Cancels a task. radkit's server does not implement `tasks/cancel`, so
+    /// the task is marked `canceled` here, with a final status event, and the
+    /// skill stops at its next step. Whatever status the skill reports
+    /// afterwards is discarded. Tasks that already finished are returned
+    /// unchanged.
+    pub async fn cancel(&self, auth: &AuthContext, task_id: &str) -> AgentResult<Option<Task>> {
+        let Some(mut task) = self.inner.get_task(auth, task_id).await? else {
+            return Ok(None);
+        };
+        if matches!(
+            task.status.state,
+            A2ATaskState::Completed | A2ATaskState::Canceled | A2ATaskState::Failed | A2ATaskState::Rejected
+        ) {
+            return Ok(Some(task));
+        }
+
+        self.cancelled.lock().unwrap().insert(task_id.to_string());
+        self.finish(auth, &task);
+        task.status = TaskStatus {
+            state: A2ATaskState::Canceled,
+            timestamp: Some(chrono::Utc::now().to_rfc3339()),
+            message: None,
+        };
+        self.inner.save_task(auth, &task).await?;
+        let event = TaskEvent::StatusUpdate(TaskStatusUpdateEvent {
+            kind: a2a_types::STATUS_UPDATE_KIND.to_string(),
+            task_id: task.id.clone(),
+            context_id: task.context_id.clone(),
+            status: task.status.clone(),
+            is_final: true,
+            metadata: None,
+        });
+        self.inner.append_event(auth, task_id, &event).await?;
+        Ok(Some(task))
+    }
+
+    pub fn is_cancelled(&self, task_id: &str) -> bool {
+        self.cancelled.lock().unwrap().contains(task_id)
+    }
+
+    fn context_key(auth: &AuthContext, context_id: &str) -> String {
+        format!("{}:{}:{}", auth.app_name, auth.user_name, context_id)
+    }
+
+    /// Records `task` as the one running in its context, unless another task
+    /// already is.
+    fn start(&self, auth: &AuthContext, task: &Task) -> AgentResult<()> {
+        let mut running = self.running.lock().unwrap();
+        let key = Self::context_key(auth, &task.context_id);
+        match running.get(&key) {
+            Some(other) if *other != task.id => Err(AgentError::InvalidInput(format!(
+                "Task {} is still running in context {}; wait for it to finish or cancel it",
+                other, task.context_id
+            ))),
+            _ => {
+                running.insert(key, task.id.clone());
+                Ok(())
+            }
+        }
+    }
+
+    /// Clears `task` as the one running in its context.
+    fn finish(&self, auth: &AuthContext, task: &Task) {
+        let mut running = self.running.lock().unwrap();
+        let key = Self::context_key(auth, &task.context_id);
+        if running.get(&key) == Some(&task.id) {
+            running.remove(&key);
+        }
+    }
+}
+
+/// Whether `update` carries a piece of a streamed reply, sent by the chat
+/// skill as a [`TEXT_DELTA_TYPE`] data part.
+fn is_text_delta(update: &TaskStatusUpdateEvent) -> bool {
+    update.status.message.as_ref().is_some_and(|message| {
+        message
+            .parts
+            .iter()
+            .any(|part| matches!(part, Part::Data { data, .. } if data["type"] == TEXT_DELTA_TYPE))
+    })
+}
+
+#[async_trait::async_trait]
+impl TaskStore for IdentifiedTaskStore {
+    async fn get_task(&self, auth: &AuthContext, task_id: &str) -> AgentResult<Option<Task>> {
+        self.inner.get_task(auth, task_id).await
+    }
+
+    async fn list_tasks(&self, auth: &AuthContext) -> AgentResult<Vec<Task>> {
+        self.inner.list_tasks(auth).await
+    }
+
+    async fn save_task(&self, auth: &AuthContext, task: &Task) -> AgentResult<()> {
+        if self.is_cancelled(&task.id) {
+            let mut task = task.clone();
+            if let Some(stored) = self.inner.get_task(auth, &task.id).await? {
+                task.status = stored.status;
+            }
+            return self.inner.save_task(auth, &task).await;
+        }
+        if task.status.state == A2ATaskState::Working {
+            self.start(auth, task)?;
+        } else {
+            self.finish(auth, task);
+        }
+        self.inner.save_task(auth, task).await
+    }
+
+    async fn append_event(&self, auth: &AuthContext, task_key: &str, event: &TaskEvent) -> AgentResult<()> {
+        if let TaskEvent::StatusUpdate(update) = event {
+            // A streamed reply's pieces are only kept until the final
+            // message, which holds the whole text, is saved.
+            if self.is_cancelled(&update.task_id) || is_text_delta(update) {
+                return Ok(());
+            }
+        }
+        self.inner.append_event(auth, task_key, event).await
+    }
+
+    async fn get_events(&self, auth: &AuthContext, task_key: &str) -> AgentResult<Vec<TaskEvent>> {
+        self.inner.get_events(auth, task_key).await
+    }
+
+    async fn list_event_task_keys(&self, auth: &AuthContext) -> AgentResult<Vec<String>> {
+        self.inner.list_event_task_keys(auth).await
+    }
+
+    async fn list_task_ids(&self, auth: &AuthContext) -> AgentResult<Vec<String>> {
+        self.inner.list_task_ids(auth).await
+    }
+
+    async fn list_context_ids(&self, auth: &AuthContext) -> AgentResult<Vec<String>> {
+        self.inner.list_context_ids(auth).await
+    }
+
+    async fn save_task_state(&self, auth: &AuthContext, task_id: &str, state: &TaskState) -> AgentResult<()> {
+        self.inner.save_task_state(auth, task_id, state).await
+    }
+
+    async fn load_task_state(&self, auth: &AuthContext, task_id: &str) -> AgentResult<Option<TaskState>> {
+        self.inner.load_task_state(auth, task_id).await
+    }
+
+    async fn set_task_skill(&self, auth: &AuthContext, task_id: &str, skill_id: &str) -> AgentResult<()> {
+        self.inner.set_task_skill(auth, task_id, skill_id).await
+    }
+
+    async fn get_task_skill(&self, auth: &AuthContext, task_id: &str) -> AgentResult<Option<String>> {
+        self.inner.get_task_skill(auth, task_id).await
+    }
+
+    async fn save_session_state(&self, auth: &AuthContext, context_id: &str, state: &SessionState) -> AgentResult<()> {
+        // A cancelled task no longer owns its context, and another task may
+        // already have saved the conversation since.
+        if let Some(task_id) = state.load::<String>(TASK_ID_KEY)? {
+            if self.is_cancelled(&task_id) {
+                return Ok(());
+            }
+        }
+        let mut state = state.clone();
+        state.remove(TASK_ID_KEY);
+        state.remove(CONTEXT_ID_KEY);
+        self.inner.save_session_state(auth, context_id, &state).await
+    }
+
+    async fn load_session_state(&self, auth: &AuthContext, context_id: &str) -> AgentResult<Option<SessionState>> {
+        let mut state = self.inner.load_session_state(auth, context_id).await?.unwrap_or_default();
+        state.save(CONTEXT_ID_KEY, &context_id)?;
+        let running = self.running.lock().unwrap().get(&Self::context_key(auth, context_id)).cloned();
+        if let Some(task_id) = running {
+            state.save(TASK_ID_KEY, &task_id)?;
+        }
+        Ok(Some(state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth() -> AuthContext {
+        AuthContext {
+            app_name: "app".into(),
+            user_name: "user".into(),
+        }
+    }
+
+    /// A fresh journal path in the temporary directory.
+    fn journal() -> PathBuf {
+        std::env::temp_dir().join(format!("radkit-tasks-{}.jsonl", uuid::Uuid::new_v4()))
+    }
+
+    fn task(id: &str, context_id: &str, state: A2ATaskState) -> Task {
+        Task {
+            id: id.into(),
+            context_id: context_id.into(),
+            status: TaskStatus {
+                state,
+                timestamp: None,
+                message: None,
+            },
+            artifacts: Vec::new(),
+        }
+    }
+
+    fn status_event(task: &Task) -> TaskEvent {
+        TaskEvent::StatusUpdate(TaskStatusUpdateEvent {
+            kind: a2a_types::STATUS_UPDATE_KIND.to_string(),
+            task_id: task.id.clone(),
+            context_id: task.context_id.clone(),
+            status: task.status.clone(),
+            is_final: false,
+            metadata: None,
+        })
+    }
+
+    fn line_count(path: &Path) -> usize {
+        std::fs::read_to_string(path).unwrap().lines().count()
+    }
+
+    #[tokio::test]
+    async fn journal_survives_a_restart_and_is_compacted() {
+        let path = journal();
+        let store = FileTaskStore::open(&path).await.unwrap();
+        let working = task("t1", "c1", A2ATaskState::Working);
+        store.save_task(&auth(), &working).await.unwrap();
+        store.append_event(&auth(), "t1", &status_event(&working)).await.unwrap();
+        store.set_task_skill(&auth(), "t1", "chat").await.unwrap();
+        let mut session = SessionState::new();
+        session.save("history", &vec!["Hello"]).unwrap();
+        store.save_session_state(&auth(), "c1", &session).await.unwrap();
+        let completed = task("t1", "c1", A2ATaskState::Completed);
+        store.save_task(&auth(), &completed).await.unwrap();
+        store.append_event(&auth(), "t1", &status_event(&completed)).await.unwrap();
+        drop(store);
+        assert_eq!(line_count(&path), 6);
+
+        let store = FileTaskStore::open(&path).await.unwrap();
+        let restored = store.get_task(&auth(), "t1").await.unwrap().unwrap();
+        assert_eq!(restored.status.state, A2ATaskState::Completed);
+        assert_eq!(store.get_events(&auth(), "t1").await.unwrap().len(), 2);
+        assert_eq!(store.get_task_skill(&auth(), "t1").await.unwrap().as_deref(), Some("chat"));
+        let session = store.load_session_state(&auth(), "c1").await.unwrap().unwrap();
+        assert_eq!(session.load::<Vec<String>>("history").unwrap(), Some(vec!["Hello".to_string()]));
+        // The first version of the task was superseded.
+        assert_eq!(line_count(&path), 5);
+
+        store.save_task(&auth(), &task("t2", "c1", A2ATaskState::Working)).await.unwrap();
+        drop(store);
+        let store = FileTaskStore::open(&path).await.unwrap();
+        assert_eq!(store.list_task_ids(&auth()).await.unwrap().len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn truncated_last_line_is_skipped() {
+        let path = journal();
+        let store = FileTaskStore::open(&path).await.unwrap();
+        store.save_task(&auth(), &task("t1", "c1", A2ATaskState::Completed)).await.unwrap();
+        drop(store);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"op\":\"saveTask\",\"appName\":\"ap").unwrap();
+        drop(file);
+
+        let store = FileTaskStore::open(&path).await.unwrap();
+        assert!(store.get_task(&auth(), "t1").await.unwrap().is_some());
+        assert_eq!(line_count(&path), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn damaged_line_before_the_end_is_an_error() {
+        let path = journal();
+        let store = FileTaskStore::open(&path).await.unwrap();
+        store.save_task(&auth(), &task("t1", "c1", A2ATaskState::Completed)).await.unwrap();
+        drop(store);
+        let journal = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, format!("not json\n{}", journal)).unwrap();
+
+        let error = FileTaskStore::open(&path).await.err().unwrap();
+        assert!(error.to_string().contains("Unreadable line 1"), "{}", error);
+        // The journal is left as it was for inspection.
+        assert_eq!(line_count(&path), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn second_task_in_a_running_context_is_rejected() {
+        let store = IdentifiedTaskStore::new(InMemoryTaskStore::new(), CancelledTasks::default());
+        store.save_task(&auth(), &task("t1", "c1", A2ATaskState::Working)).await.unwrap();
+        let error = store.save_task(&auth(), &task("t2", "c1", A2ATaskState::Working)).await.unwrap_err();
+        assert!(matches!(error, AgentError::InvalidInput(_)));
+        store.save_task(&auth(), &task("t3", "c2", A2ATaskState::Working)).await.unwrap();
+
+        let session = store.load_session_state(&auth(), "c1").await.unwrap().unwrap();
+        assert_eq!(session.load::<String>(TASK_ID_KEY).unwrap().as_deref(), Some("t1"));
+
+        store.save_task(&auth(), &task("t1", "c1", A2ATaskState::Completed)).await.unwrap();
+        store.save_task(&auth(), &task("t2", "c1", A2ATaskState::Working)).await.unwrap();
+        let session = store.load_session_state(&auth(), "c1").await.unwrap().unwrap();
+        assert_eq!(session.load::<String>(TASK_ID_KEY).unwrap().as_deref(), Some("t2"));
+    }
+}